@@ -47,7 +47,7 @@ impl<'info> Initialize<'info> {
             authority: self.authority.key(),
             token_mint: self.token_mint.key(),
             reserve_account: self.reserve_token_account.key(),
-            apy: apy,
+            apy,
             bump: bumps.yield_reserve,
         });
 
@@ -25,4 +25,175 @@ pub enum VaultPayError {
 
     #[msg("Insufficient Funds")]
     InsufficientFunds,
+
+    #[msg("Billing interval must be greater than zero")]
+    InvalidInterval,
+
+    #[msg("Payment is not due yet")]
+    PaymentNotDue,
+
+    #[msg("Amount per payment must be greater than zero")]
+    InvalidAmount,
+
+    #[msg("Number of payments must be greater than zero")]
+    InvalidNumberOfPayments,
+
+    #[msg("Subscription start time is in the past")]
+    StartTimeInPast,
+
+    #[msg("Subscription duration is below the configured minimum")]
+    SubscriptionTooShort,
+
+    #[msg("Subscription duration exceeds the configured maximum")]
+    SubscriptionTooLong,
+
+    #[msg("Vendor is not approved")]
+    VendorNotApproved,
+
+    #[msg("Invalid vendor status transition")]
+    InvalidVendorStatus,
+
+    #[msg("Unauthorized")]
+    Unauthorized,
+
+    #[msg("Platform fee exceeds the maximum allowed")]
+    PlatformFeeTooHigh,
+
+    #[msg("Invalid subscription duration bounds")]
+    InvalidDurationBounds,
+
+    #[msg("No authority transfer is pending")]
+    NoPendingAuthority,
+
+    #[msg("The program is paused")]
+    ProgramPaused,
+
+    #[msg("The subscription is frozen")]
+    SubscriptionFrozen,
+
+    #[msg("Invalid payout wallet")]
+    InvalidPayoutWallet,
+
+    #[msg("Trial period cannot be negative")]
+    InvalidTrialPeriod,
+
+    #[msg("The plan is not active")]
+    PlanNotActive,
+
+    #[msg("The subscription is paused")]
+    SubscriptionPaused,
+
+    #[msg("The subscription is not paused")]
+    SubscriptionNotPaused,
+
+    #[msg("Pause duration cannot be negative")]
+    InvalidPauseDuration,
+
+    #[msg("Retention period cannot be negative")]
+    InvalidRetentionPeriod,
+
+    #[msg("The subscription has not been cancelled")]
+    SubscriptionNotCancelled,
+
+    #[msg("The subscription has not ended")]
+    SubscriptionNotEnded,
+
+    #[msg("The cancellation record is still within its retention period")]
+    RetentionPeriodActive,
+
+    #[msg("Invalid cancellation reason")]
+    InvalidCancellationReason,
+
+    #[msg("Invalid refund account")]
+    InvalidRefundAccount,
+
+    #[msg("Grace period cannot be negative")]
+    InvalidGracePeriod,
+
+    #[msg("Keeper fee cannot exceed the platform fee")]
+    InvalidKeeperFee,
+
+    #[msg("Batch accounts must come in groups of six")]
+    InvalidBatchAccounts,
+
+    #[msg("The subscription is still in its trial period")]
+    TrialNotEnded,
+
+    #[msg("The new plan must bill on the same interval")]
+    PlanIntervalMismatch,
+
+    #[msg("Price changes must give at least the minimum notice")]
+    InvalidEffectiveDate,
+
+    #[msg("There is no pending price change")]
+    NoPendingPriceChange,
+
+    #[msg("The price change took effect before it was accepted")]
+    PriceChangeExpired,
+
+    #[msg("The charge exceeds a spending cap set on the subscription")]
+    SpendingCapExceeded,
+
+    #[msg("The charge exceeds the spending cap set on the user vault")]
+    VaultSpendingCapExceeded,
+
+    #[msg("Spending window must be greater than zero")]
+    InvalidSpendingWindow,
+
+    #[msg("Operation does not match the subscription's billing mode")]
+    BillingModeMismatch,
+
+    #[msg("Usage has not been reported for the current period")]
+    UsageNotReported,
+
+    #[msg("The stream has already been stopped")]
+    StreamStopped,
+
+    #[msg("Nothing has accrued since the last withdrawal")]
+    NothingToWithdraw,
+
+    #[msg("Invoice memo is too long")]
+    InvalidMemo,
+
+    #[msg("Invoice must expire in the future and after it is due")]
+    InvalidInvoiceDates,
+
+    #[msg("The invoice is no longer open")]
+    InvoiceNotOpen,
+
+    #[msg("The invoice has expired")]
+    InvoiceExpired,
+
+    #[msg("The invoice has not expired yet")]
+    InvoiceNotExpired,
+
+    #[msg("Revenue splits must be non-zero and sum to 10,000 basis points")]
+    InvalidRevenueSplits,
+
+    #[msg("Payee accounts do not match the vendor's revenue splits")]
+    InvalidRevenueSplitAccounts,
+
+    #[msg("Keeper and referral shares cannot exceed the platform fee")]
+    InvalidReferralFee,
+
+    #[msg("Affiliate does not match the subscription's referrer")]
+    InvalidAffiliate,
+
+    #[msg("The affiliate is not active")]
+    AffiliateNotActive,
+
+    #[msg("Retry interval cannot be negative")]
+    InvalidRetryInterval,
+
+    #[msg("A failed charge cannot be retried yet")]
+    RetryTooSoon,
+
+    #[msg("Price notice cannot be negative")]
+    InvalidPriceNotice,
+
+    #[msg("Invoice is not due yet")]
+    InvoiceNotDue,
+
+    #[msg("Invoice is still open")]
+    InvoiceStillOpen,
 }
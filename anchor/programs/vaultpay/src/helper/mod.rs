@@ -0,0 +1,474 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, transfer_checked, TransferChecked};
+use mock_yield_source::cpi::accounts::Withdraw as YieldSourceWithdraw;
+use mock_yield_source::states::YieldAccount;
+
+use crate::errors::VaultPayError;
+use crate::states::{
+    Affiliate, BillingMode, Config, Subscription, SubscriptionStatus, UserVault, Vendor,
+};
+
+/// A user's yield position and the vaultpay authority PDA that signs for it.
+/// Payments out of a vault are withdrawn into the authority's token account
+/// and paid out from there. Built with `VaultAccounts::new` so every charge
+/// and withdrawal goes through the same checks.
+#[derive(Clone)]
+pub struct VaultAccounts<'info> {
+    config: Pubkey,
+    user: Pubkey,
+    authority_bump: u8,
+    token_mint: InterfaceAccount<'info, Mint>,
+    vaultpay_authority: AccountInfo<'info>,
+    vaultpay_authority_ata: AccountInfo<'info>,
+    yield_reserve: AccountInfo<'info>,
+    yield_account: AccountInfo<'info>,
+    yield_token_account: AccountInfo<'info>,
+    reserve_token_account: AccountInfo<'info>,
+    yield_program: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    associated_token_program: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+}
+
+impl<'info> VaultAccounts<'info> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        config: Pubkey,
+        user: Pubkey,
+        authority_bump: u8,
+        token_mint: &InterfaceAccount<'info, Mint>,
+        vaultpay_authority: AccountInfo<'info>,
+        vaultpay_authority_ata: AccountInfo<'info>,
+        yield_reserve: AccountInfo<'info>,
+        yield_account: AccountInfo<'info>,
+        yield_token_account: AccountInfo<'info>,
+        reserve_token_account: AccountInfo<'info>,
+        yield_program: AccountInfo<'info>,
+        token_program: AccountInfo<'info>,
+        associated_token_program: AccountInfo<'info>,
+        system_program: AccountInfo<'info>,
+    ) -> Self {
+        Self {
+            config,
+            user,
+            authority_bump,
+            token_mint: token_mint.clone(),
+            vaultpay_authority,
+            vaultpay_authority_ata,
+            yield_reserve,
+            yield_account,
+            yield_token_account,
+            reserve_token_account,
+            yield_program,
+            token_program,
+            associated_token_program,
+            system_program,
+        }
+    }
+
+    pub fn signer_seeds(&self) -> [&[u8]; 4] {
+        [
+            b"vaultpay_authority",
+            self.config.as_ref(),
+            self.user.as_ref(),
+            std::slice::from_ref(&self.authority_bump),
+        ]
+    }
+
+    /// Amount that can be withdrawn from the yield position right now.
+    /// The yield source only releases up to the deposited principal.
+    /// Fails unless the position belongs to this vault's authority, so a short
+    /// balance is never read off somebody else's position.
+    pub fn available_balance(&self) -> Result<u64> {
+        require_keys_eq!(
+            *self.yield_account.owner,
+            mock_yield_source::ID,
+            VaultPayError::InvalidYieldAccount
+        );
+        let deposited_amount = {
+            let data = self.yield_account.try_borrow_data()?;
+            let position = YieldAccount::try_deserialize(&mut &data[..])?;
+            require_keys_eq!(
+                position.owner,
+                self.vaultpay_authority.key(),
+                VaultPayError::InvalidYieldAccount
+            );
+            position.deposited_amount
+        };
+        let data = self.yield_token_account.try_borrow_data()?;
+        let yield_token_account = TokenAccount::try_deserialize(&mut &data[..])?;
+
+        Ok(deposited_amount.min(yield_token_account.amount))
+    }
+
+    /// Pulls `amount` out of the yield position into the authority's token account
+    pub fn withdraw(&self, amount: u64) -> Result<()> {
+        let seeds = self.signer_seeds();
+        let signer_seeds = &[&seeds[..]];
+        let cpi_accounts = YieldSourceWithdraw {
+            user: self.vaultpay_authority.clone(),
+            token_mint: self.token_mint.to_account_info(),
+            user_token_account: self.vaultpay_authority_ata.clone(),
+            yield_reserve: self.yield_reserve.clone(),
+            yield_account: self.yield_account.clone(),
+            yield_token_account: self.yield_token_account.clone(),
+            reserve_token_account: self.reserve_token_account.clone(),
+            token_program: self.token_program.clone(),
+            system_program: self.system_program.clone(),
+            associated_token_program: self.associated_token_program.clone(),
+        };
+
+        let cpi_ctx =
+            CpiContext::new_with_signer(self.yield_program.clone(), cpi_accounts, signer_seeds);
+        mock_yield_source::cpi::withdraw(cpi_ctx, amount)
+    }
+
+    /// Pays `amount` out of the authority's token account
+    pub fn transfer(&self, to: AccountInfo<'info>, amount: u64) -> Result<()> {
+        transfer_signed(
+            self.token_program.clone(),
+            &self.token_mint,
+            self.vaultpay_authority_ata.clone(),
+            to,
+            self.vaultpay_authority.clone(),
+            &[&self.signer_seeds()],
+            amount,
+        )
+    }
+}
+
+/// Where the proceeds of a charge go
+pub struct Payout<'a, 'info> {
+    pub vendor: &'a Vendor,
+    pub vendor_token_account: AccountInfo<'info>,
+    /// Revenue split payees, in the vendor's table order
+    pub payees: &'a [AccountInfo<'info>],
+    pub treasury_token_account: AccountInfo<'info>,
+    /// Set when a keeper cranked the charge and earns its tip
+    pub keeper_token_account: Option<AccountInfo<'info>>,
+    pub affiliate: Option<&'a mut Account<'info, Affiliate>>,
+    pub affiliate_escrow: Option<AccountInfo<'info>>,
+}
+
+/// Result of the checks made before a subscription is charged
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ChargeCheck {
+    /// The amount can be collected
+    Due(u64),
+    /// The vault is short, the miss has been recorded on the subscription
+    InsufficientFunds,
+    /// An unaccepted price change took effect and ended the subscription
+    Cancelled,
+}
+
+/// Checks shared by every path that charges a subscription. A pause that
+/// outlived the configured cap resumes billing automatically.
+pub fn ensure_payment_due(
+    subscription: &mut Subscription,
+    vendor_key: Pubkey,
+    vendor: &Vendor,
+    config: &Config,
+    now: i64,
+) -> Result<()> {
+    require!(!config.locked, VaultPayError::ProgramPaused);
+    require!(!subscription.locked, VaultPayError::SubscriptionFrozen);
+
+    if subscription.status == SubscriptionStatus::Paused {
+        require!(
+            subscription.pause_expired(now, config.max_pause_duration),
+            VaultPayError::SubscriptionPaused
+        );
+        subscription.resume(now, config.max_pause_duration)?;
+    }
+
+    require!(subscription.is_chargeable(), VaultPayError::SubscriptionNotActive);
+
+    // Ensure subscription vendor is same as given account vendor
+    require_keys_eq!(subscription.vendor, vendor_key, VaultPayError::InvalidVendor);
+    require!(vendor.is_in_good_standing(), VaultPayError::VendorNotApproved);
+
+    require!(!subscription.in_trial(now), VaultPayError::TrialNotEnded);
+
+    // Ensure the current billing period is due
+    require!(now >= subscription.next_payment_due, VaultPayError::PaymentNotDue);
+
+    // Failed charges are retried at most once per retry interval
+    if subscription.status == SubscriptionStatus::PastDue {
+        let retry_at = subscription.last_attempt_at
+            .checked_add(config.retry_interval)
+            .ok_or(VaultPayError::MathOverflow)?;
+        require!(now >= retry_at, VaultPayError::RetryTooSoon);
+    }
+    Ok(())
+}
+
+/// Splits a payment into the platform fee (in basis points) and the vendor's share
+pub fn split_platform_fee(amount: u64, platform_fee_bps: u16) -> Result<(u64, u64)> {
+    let platform_fee = bps_of(amount, platform_fee_bps)?;
+    let amount_to_vendor = amount
+        .checked_sub(platform_fee)
+        .ok_or(VaultPayError::MathUnderflow)?;
+    Ok((platform_fee, amount_to_vendor))
+}
+
+pub fn bps_of(amount: u64, bps: u16) -> Result<u64> {
+    let share = (amount as u128)
+        .checked_mul(bps as u128)
+        .ok_or(VaultPayError::MathOverflow)?
+        .checked_div(10_000)
+        .ok_or(VaultPayError::MathOverflow)?;
+    Ok(share as u64)
+}
+
+/// Moves tokens out of an account owned by a program PDA, skipping empty transfers
+pub fn transfer_signed<'info>(
+    token_program: AccountInfo<'info>,
+    token_mint: &InterfaceAccount<'info, Mint>,
+    from: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    transfer_checked(
+        CpiContext::new_with_signer(
+            token_program,
+            TransferChecked {
+                from,
+                to,
+                mint: token_mint.to_account_info(),
+                authority,
+            },
+            signer_seeds,
+        ),
+        amount,
+        token_mint.decimals,
+    )
+}
+
+/// Moves tokens out of an account whose authority signed the transaction,
+/// skipping empty transfers
+pub fn transfer_unsigned<'info>(
+    token_program: AccountInfo<'info>,
+    token_mint: &InterfaceAccount<'info, Mint>,
+    from: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    transfer_signed(token_program, token_mint, from, to, authority, &[], amount)
+}
+
+/// Pays the vendor's share of a charge. With a revenue split table the share
+/// goes to the payees in `payees`, in table order, and the last payee takes
+/// any rounding remainder.
+#[allow(clippy::too_many_arguments)]
+pub fn pay_vendor<'info>(
+    token_program: AccountInfo<'info>,
+    token_mint: &InterfaceAccount<'info, Mint>,
+    from: AccountInfo<'info>,
+    vendor_wallet: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    vendor: &Vendor,
+    payees: &[AccountInfo<'info>],
+    amount: u64,
+) -> Result<()> {
+    if vendor.revenue_splits.is_empty() {
+        return transfer_signed(
+            token_program,
+            token_mint,
+            from,
+            vendor_wallet,
+            authority,
+            signer_seeds,
+            amount,
+        );
+    }
+
+    require!(
+        payees.len() == vendor.revenue_splits.len(),
+        VaultPayError::InvalidRevenueSplitAccounts
+    );
+    let mut remaining = amount;
+    for (index, (split, payee)) in vendor.revenue_splits.iter().zip(payees).enumerate() {
+        require_keys_eq!(
+            payee.key(),
+            split.token_account,
+            VaultPayError::InvalidRevenueSplitAccounts
+        );
+        let share = if index == payees.len() - 1 {
+            remaining
+        } else {
+            bps_of(amount, split.bps)?
+        };
+        remaining = remaining
+            .checked_sub(share)
+            .ok_or(VaultPayError::MathUnderflow)?;
+
+        transfer_signed(
+            token_program.clone(),
+            token_mint,
+            from.clone(),
+            payee.clone(),
+            authority.clone(),
+            signer_seeds,
+            share,
+        )?;
+    }
+    Ok(())
+}
+
+/// Checks made before a subscription is charged: settles a pending price
+/// change, bills metered usage and records a miss when the vault is short.
+/// Spending caps are only counted once the charge is known to clear.
+#[allow(clippy::too_many_arguments)]
+pub fn prepare_charge(
+    subscription: &mut Subscription,
+    user_vault: &mut UserVault,
+    vendor_key: Pubkey,
+    vendor: &Vendor,
+    config: &Config,
+    usage_units: Option<u64>,
+    available: u64,
+    now: i64,
+) -> Result<ChargeCheck> {
+    if !config.locked && subscription.settle_price_change(now) {
+        msg!("Price change not accepted, subscription cancelled");
+        return Ok(ChargeCheck::Cancelled);
+    }
+
+    ensure_payment_due(subscription, vendor_key, vendor, config, now)?;
+
+    // Metered subscriptions bill the usage reported for this period
+    if let BillingMode::Metered { .. } = subscription.billing_mode {
+        let units = usage_units.ok_or(VaultPayError::UsageNotReported)?;
+        subscription.apply_usage(units)?;
+    }
+
+    // A short vault would fail the withdrawal and revert, record the miss instead
+    let amount = subscription.amount_due();
+    if available < amount {
+        subscription.record_failed_payment(now, config.grace_period, config.max_retries);
+        msg!("Insufficient funds, failed attempts: {}", subscription.failed_attempts);
+        return Ok(ChargeCheck::InsufficientFunds);
+    }
+
+    subscription.record_spend(amount)?;
+    user_vault.record_spend(amount, now)?;
+    Ok(ChargeCheck::Due(amount))
+}
+
+/// Withdraws `amount` from the vault and pays it out: the vendor's share to
+/// its wallet or payees, the keeper tip and referral share out of the platform
+/// fee, and the rest of the fee to the treasury.
+pub fn collect_payment<'info>(
+    vault: &VaultAccounts<'info>,
+    config: &Config,
+    payout: Payout<'_, 'info>,
+    amount: u64,
+    referral_due: bool,
+) -> Result<()> {
+    // Keeper tip and referral come out of the platform fee, never out of the vendor's share
+    let (platform_fee, amount_to_vendor) = split_platform_fee(amount, config.platform_fee)?;
+    let keeper_tip = match payout.keeper_token_account {
+        Some(_) => bps_of(platform_fee, config.keeper_fee)?,
+        None => 0,
+    };
+    let referral = match referral_due {
+        true => bps_of(platform_fee, config.referral_fee)?,
+        false => 0,
+    };
+    let treasury_fee = platform_fee
+        .checked_sub(keeper_tip)
+        .and_then(|fee| fee.checked_sub(referral))
+        .ok_or(VaultPayError::MathUnderflow)?;
+    msg!(
+        "Platform Fee: {}, Keeper Tip: {}, Referral: {}",
+        platform_fee,
+        keeper_tip,
+        referral
+    );
+
+    let affiliate = match (payout.affiliate, payout.affiliate_escrow) {
+        (Some(affiliate), Some(escrow)) => Some((affiliate, escrow)),
+        _ if referral > 0 => return err!(VaultPayError::InvalidAffiliate),
+        _ => None,
+    };
+
+    vault.withdraw(amount)?;
+
+    // Transfer to Vendor, split across its payees when configured
+    pay_vendor(
+        vault.token_program.clone(),
+        &vault.token_mint,
+        vault.vaultpay_authority_ata.clone(),
+        payout.vendor_token_account,
+        vault.vaultpay_authority.clone(),
+        &[&vault.signer_seeds()],
+        payout.vendor,
+        payout.payees,
+        amount_to_vendor,
+    )?;
+
+    // Transfer the rest of the platform fee to Treasury
+    vault.transfer(payout.treasury_token_account, treasury_fee)?;
+
+    if let Some(keeper_token_account) = payout.keeper_token_account {
+        vault.transfer(keeper_token_account, keeper_tip)?;
+    }
+
+    // Credit the referrer's share to its escrow, where it waits to be claimed
+    if let Some((affiliate, escrow)) = affiliate {
+        if referral > 0 {
+            vault.transfer(escrow, referral)?;
+            affiliate.total_earned = affiliate.total_earned
+                .checked_add(referral)
+                .ok_or(VaultPayError::MathOverflow)?;
+        }
+    }
+    Ok(())
+}
+
+/// Charges the amount due on a subscription and moves its schedule to the
+/// next period. Shared by `process_payment` and the keeper's `crank_payment`.
+#[allow(clippy::too_many_arguments)]
+pub fn charge_subscription<'info>(
+    subscription: &mut Subscription,
+    user_vault: &mut UserVault,
+    vendor_key: Pubkey,
+    config: &Config,
+    vault: &VaultAccounts<'info>,
+    payout: Payout<'_, 'info>,
+    usage_units: Option<u64>,
+    now: i64,
+) -> Result<()> {
+    let available = vault.available_balance()?;
+    let check = prepare_charge(
+        subscription,
+        user_vault,
+        vendor_key,
+        payout.vendor,
+        config,
+        usage_units,
+        available,
+        now,
+    )?;
+    let ChargeCheck::Due(amount) = check else {
+        return Ok(());
+    };
+
+    let referral_due = subscription.earns_referral(config.referral_payments);
+    collect_payment(vault, config, payout, amount, referral_due)?;
+
+    // Update subscription and move the schedule to the next period
+    subscription.record_payment(amount, now)?;
+    if subscription.status == SubscriptionStatus::Completed {
+        msg!("Subscription status updated to Completed");
+    }
+    Ok(())
+}
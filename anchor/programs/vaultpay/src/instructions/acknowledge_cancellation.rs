@@ -0,0 +1,49 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::states::{Config, Subscription, SubscriptionStatus, Vendor};
+use crate::errors::VaultPayError;
+
+#[derive(Accounts)]
+pub struct AcknowledgeCancellation<'info> {
+    pub vendor_signer: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"vendor", config.key().as_ref(), vendor_signer.key().as_ref()],
+        bump = vendor.bump,
+    )]
+    pub vendor: Account<'info, Vendor>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            vendor.key().as_ref(),
+            subscription.user.key().as_ref(),
+            subscription.seed.to_le_bytes().as_ref(),
+        ],
+        bump = subscription.bump,
+        has_one = vendor,
+    )]
+    pub subscription: Account<'info, Subscription>,
+}
+
+impl<'info> AcknowledgeCancellation<'info> {
+    /// Lets the user close the cancelled subscription before the retention window ends
+    pub fn acknowledge_cancellation(&mut self) -> Result<()> {
+        require!(
+            self.subscription.status == SubscriptionStatus::Cancelled,
+            VaultPayError::SubscriptionNotCancelled
+        );
+        self.subscription.vendor_acknowledged = true;
+        msg!("Cancellation acknowledged: {}", self.subscription.key());
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::states::{Affiliate, Config};
+use crate::errors::VaultPayError;
+use crate::helper::transfer_signed;
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct RegisterAffiliate<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+        has_one = authority @ VaultPayError::Unauthorized,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Affiliate::LEN,
+        seeds = [b"affiliate", config.key().as_ref(), wallet.as_ref()],
+        bump,
+    )]
+    pub affiliate: Box<Account<'info, Affiliate>>,
+
+    // Referral shares accrue here until the affiliate claims them
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = token_mint,
+        associated_token::authority = affiliate,
+    )]
+    pub affiliate_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RegisterAffiliate<'info> {
+    pub fn register_affiliate(&mut self, wallet: Pubkey, bumps: &RegisterAffiliateBumps) -> Result<()> {
+        self.affiliate.set_inner(Affiliate {
+            wallet,
+            active: true,
+            total_earned: 0,
+            total_claimed: 0,
+            bump: bumps.affiliate,
+        });
+        msg!("Affiliate registered: {}", self.affiliate.key());
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct RevokeAffiliate<'info> {
+    pub authority: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+        has_one = authority @ VaultPayError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"affiliate", config.key().as_ref(), affiliate.wallet.as_ref()],
+        bump = affiliate.bump,
+    )]
+    pub affiliate: Account<'info, Affiliate>,
+}
+
+impl<'info> RevokeAffiliate<'info> {
+    /// Stops new referrals, existing subscriptions keep paying out and earnings stay claimable
+    pub fn revoke_affiliate(&mut self) -> Result<()> {
+        require!(self.affiliate.active, VaultPayError::AffiliateNotActive);
+        self.affiliate.active = false;
+        msg!("Affiliate revoked: {}", self.affiliate.key());
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ClaimAffiliateEarnings<'info> {
+    pub wallet: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [b"affiliate", config.key().as_ref(), wallet.key().as_ref()],
+        bump = affiliate.bump,
+        has_one = wallet @ VaultPayError::Unauthorized,
+    )]
+    pub affiliate: Box<Account<'info, Affiliate>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = affiliate,
+    )]
+    pub affiliate_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+    )]
+    pub destination_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> ClaimAffiliateEarnings<'info> {
+    pub fn claim_affiliate_earnings(&mut self) -> Result<()> {
+        let amount = self.affiliate_escrow.amount;
+        require!(amount > 0, VaultPayError::NothingToWithdraw);
+
+        let binding_config = self.config.key();
+        let binding_wallet = self.wallet.key();
+        let seeds = &[
+            b"affiliate",
+            binding_config.as_ref(),
+            binding_wallet.as_ref(),
+            &[self.affiliate.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        // Transfer the full escrow balance to the affiliate
+        transfer_signed(
+            self.token_program.to_account_info(),
+            &self.token_mint,
+            self.affiliate_escrow.to_account_info(),
+            self.destination_token_account.to_account_info(),
+            self.affiliate.to_account_info(),
+            signer_seeds,
+            amount,
+        )?;
+
+        self.affiliate.total_claimed = self.affiliate.total_claimed
+            .checked_add(amount)
+            .ok_or(VaultPayError::MathOverflow)?;
+        msg!("Affiliate earnings claimed: {}", amount);
+        Ok(())
+    }
+}
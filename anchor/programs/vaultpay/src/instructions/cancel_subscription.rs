@@ -1,6 +1,6 @@
 // instructions/cancel_subscription.rs
 use anchor_lang::prelude::*;
-use crate::states::{Subscription, SubscriptionStatus};
+use crate::states::{CancellationReason, Subscription, SubscriptionStatus};
 
 #[derive(Accounts)]
 pub struct CancelSubscription<'info> {
@@ -9,8 +9,12 @@ pub struct CancelSubscription<'info> {
 
     #[account(
         mut,
-        close = user,
-        seeds = [b"subscription", subscription.vendor.key().as_ref(), user.key().as_ref()],
+        seeds = [
+            b"subscription",
+            subscription.vendor.key().as_ref(),
+            user.key().as_ref(),
+            subscription.seed.to_le_bytes().as_ref(),
+        ],
         bump = subscription.bump,
         has_one = user,
     )]
@@ -22,10 +26,26 @@ pub struct CancelSubscription<'info> {
 impl<'info> CancelSubscription<'info> {
     pub fn cancel_subscription(&mut self) -> Result<()> {
         require!(
-            self.subscription.status == SubscriptionStatus::Active,
+            self.subscription.is_open(),
             crate::errors::VaultPayError::SubscriptionNotActive
         );
+
+        // Nothing was charged during the trial, so there is nothing to keep a record of
+        let now = Clock::get()?.unix_timestamp;
+        if self.subscription.in_trial(now) && self.subscription.payments_made == 0 {
+            self.subscription.close(self.user.to_account_info())?;
+            msg!("Subscription cancelled during trial and closed");
+            return Ok(());
+        }
+
+        // The account is kept as a record of the cancellation, see close_subscription
         self.subscription.status = SubscriptionStatus::Cancelled;
+        self.subscription.cancelled_at = now;
+        self.subscription.cancellation_reason = Some(CancellationReason::UserRequested);
+        msg!(
+            "Subscription cancelled after {} payments",
+            self.subscription.payments_made
+        );
         Ok(())
     }
 }
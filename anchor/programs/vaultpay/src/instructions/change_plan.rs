@@ -0,0 +1,226 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::states::{Affiliate, Config, Plan, Subscription, Vendor, UserVault};
+use crate::errors::VaultPayError;
+use crate::helper::{collect_payment, Payout, VaultAccounts};
+use mock_yield_source::program::MockYieldSource;
+
+#[derive(Accounts)]
+pub struct ChangePlan<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        seeds = [b"vendor", config.key().as_ref(), vendor.authority.key().as_ref()],
+        bump = vendor.bump,
+    )]
+    pub vendor: Box<Account<'info, Vendor>>,
+
+    #[account(
+        seeds = [b"plan", vendor.key().as_ref(), new_plan.seed.to_le_bytes().as_ref()],
+        bump = new_plan.bump,
+    )]
+    pub new_plan: Box<Account<'info, Plan>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            vendor.key().as_ref(),
+            user.key().as_ref(),
+            subscription.seed.to_le_bytes().as_ref(),
+        ],
+        bump = subscription.bump,
+        has_one = user,
+    )]
+    pub subscription: Box<Account<'info, Subscription>>,
+
+    /// CHECK: This is a PDA used as a signer
+    #[account(
+        mut,
+        seeds = [b"vaultpay_authority", config.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub vaultpay_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"user_vault", config.key().as_ref(), user.key().as_ref()],
+        bump = user_vault.bump,
+    )]
+    pub user_vault: Box<Account<'info, UserVault>>,
+
+    /// CHECK: This is checked in the CPI to mock_yield_source
+    #[account(mut)]
+    pub yield_account: UncheckedAccount<'info>,
+
+    /// CHECK: This is checked in the CPI to mock_yield_source
+    #[account(mut)]
+    pub yield_reserve: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = yield_account,
+    )]
+    pub yield_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = vendor.vendor_wallet,
+    )]
+    pub vendor_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = yield_reserve
+    )]
+    pub reserve_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = vaultpay_authority
+    )]
+    pub vaultpay_authority_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = config.treasury_wallet,
+    )]
+    pub treasury_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // Required while the subscription's referrer earns a share of the platform fee
+    #[account(
+        mut,
+        constraint = subscription.referrer == Some(affiliate.key()) @ VaultPayError::InvalidAffiliate,
+    )]
+    pub affiliate: Option<Box<Account<'info, Affiliate>>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = affiliate,
+    )]
+    pub affiliate_escrow: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub yield_program: Program<'info, MockYieldSource>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ChangePlan<'info> {
+    pub fn change_plan(
+        &mut self,
+        bumps: &ChangePlanBumps,
+        payees: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(!self.config.locked, VaultPayError::ProgramPaused);
+        require!(!self.subscription.locked, VaultPayError::SubscriptionFrozen);
+        require!(self.subscription.is_open(), VaultPayError::SubscriptionNotActive);
+        require_keys_eq!(self.subscription.vendor, self.vendor.key(), VaultPayError::InvalidVendor);
+        require!(self.vendor.is_in_good_standing(), VaultPayError::VendorNotApproved);
+        require!(self.new_plan.active, VaultPayError::PlanNotActive);
+
+        // Proration assumes both plans bill over the same period
+        let plan = &self.new_plan;
+        require!(
+            plan.interval_seconds == self.subscription.interval_seconds,
+            VaultPayError::PlanIntervalMismatch
+        );
+        require!(
+            std::mem::discriminant(&plan.billing_mode)
+                == std::mem::discriminant(&self.subscription.billing_mode),
+            VaultPayError::BillingModeMismatch
+        );
+        Plan::validate_terms(
+            plan.amount_per_payment,
+            plan.interval_seconds,
+            plan.number_of_payments,
+            0,
+            &self.config,
+        )?;
+
+        // The new price has to fit the cap the user set per period
+        require!(
+            self.subscription
+                .max_per_period
+                .map_or(true, |cap| plan.amount_per_payment <= cap),
+            VaultPayError::SpendingCapExceeded
+        );
+
+        // Payments already made count toward the new plan
+        if let Some(number_of_payments) = plan.number_of_payments {
+            require!(
+                number_of_payments > self.subscription.payments_made,
+                VaultPayError::InvalidNumberOfPayments
+            );
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        let amount = self.subscription.switch_plan(plan.key(), plan, now)?;
+        msg!(
+            "Plan changed, prorated charge: {}, credit balance: {}",
+            amount,
+            self.subscription.credit_balance
+        );
+        if amount == 0 {
+            return Ok(());
+        }
+
+        // Upgrades pay the difference for the rest of the period right away
+        let vault = VaultAccounts::new(
+            self.config.key(),
+            self.user.key(),
+            bumps.vaultpay_authority,
+            &self.token_mint,
+            self.vaultpay_authority.to_account_info(),
+            self.vaultpay_authority_ata.to_account_info(),
+            self.yield_reserve.to_account_info(),
+            self.yield_account.to_account_info(),
+            self.yield_token_account.to_account_info(),
+            self.reserve_token_account.to_account_info(),
+            self.yield_program.to_account_info(),
+            self.token_program.to_account_info(),
+            self.associated_token_program.to_account_info(),
+            self.system_program.to_account_info(),
+        );
+        let available = vault.available_balance()?;
+        require!(available >= amount, VaultPayError::InsufficientFunds);
+        self.subscription.record_spend(amount)?;
+        self.user_vault.record_spend(amount, now)?;
+
+        // Proration charges share the platform fee like the period they top up
+        let referral_due = self.subscription.earns_referral(self.config.referral_payments);
+        collect_payment(
+            &vault,
+            &self.config,
+            Payout {
+                vendor: &self.vendor,
+                vendor_token_account: self.vendor_token_account.to_account_info(),
+                payees,
+                treasury_token_account: self.treasury_token_account.to_account_info(),
+                keeper_token_account: None,
+                affiliate: self.affiliate.as_deref_mut(),
+                affiliate_escrow: self.affiliate_escrow.as_ref().map(|escrow| escrow.to_account_info()),
+            },
+            amount,
+            referral_due,
+        )?;
+
+        Ok(())
+    }
+}
@@ -20,7 +20,7 @@ pub struct ClaimTreasury<'info> {
     pub treasury: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
-        seeds = [b"config", supported_token.key().as_ref(), owner.key().as_ref()],
+        seeds = [b"config", supported_token.key().as_ref(), config.seed.to_le_bytes().as_ref()],
         bump,
         constraint = config.authority == owner.key(),
     )]
@@ -59,13 +59,13 @@ impl<'info> ClaimTreasury<'info> {
 
         // Prepare the seeds for signing
 
-        let binding_owner = self.owner.key();
+        let binding_seed = self.config.seed.to_le_bytes();
         let binding_supported_token = self.supported_token.key();
 
         let config_seeds = &[
             b"config",
             binding_supported_token.as_ref(),
-            binding_owner.as_ref(),
+            binding_seed.as_ref(),
             &[self.config.bump],
         ];
         let signer_seeds = &[&config_seeds[..]];
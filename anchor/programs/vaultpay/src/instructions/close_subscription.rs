@@ -0,0 +1,63 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::states::{Config, Subscription, SubscriptionStatus, Vendor};
+use crate::errors::VaultPayError;
+
+#[derive(Accounts)]
+pub struct CloseSubscription<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"vendor", config.key().as_ref(), vendor.authority.key().as_ref()],
+        bump = vendor.bump,
+    )]
+    pub vendor: Account<'info, Vendor>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [
+            b"subscription",
+            vendor.key().as_ref(),
+            user.key().as_ref(),
+            subscription.seed.to_le_bytes().as_ref(),
+        ],
+        bump = subscription.bump,
+        has_one = user,
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CloseSubscription<'info> {
+    pub fn close_subscription(&mut self) -> Result<()> {
+        match self.subscription.status {
+            SubscriptionStatus::Completed => {}
+            SubscriptionStatus::Cancelled => {
+                // Keep the record until the vendor has seen it or the retention window passed
+                let retained_until = self.subscription.cancelled_at
+                    .checked_add(self.config.cancellation_retention)
+                    .ok_or(VaultPayError::MathOverflow)?;
+                require!(
+                    self.subscription.vendor_acknowledged
+                        || Clock::get()?.unix_timestamp >= retained_until,
+                    VaultPayError::RetentionPeriodActive
+                );
+            }
+            _ => return Err(error!(VaultPayError::SubscriptionNotEnded)),
+        }
+
+        msg!("Subscription closed: {}", self.subscription.key());
+        Ok(())
+    }
+}
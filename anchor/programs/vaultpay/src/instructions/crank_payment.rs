@@ -0,0 +1,190 @@
+// instructions/crank_payment.rs
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::states::{Affiliate, Config, Subscription, Vendor, UsageReport, UserVault};
+use crate::errors::VaultPayError;
+use crate::helper::{charge_subscription, Payout, VaultAccounts};
+use mock_yield_source::program::MockYieldSource;
+
+/// Permissionless version of `ProcessPayment`. Anyone can charge a due
+/// subscription, funds still only go to the vendor's registered wallet and
+/// the keeper earns a share of the platform fee.
+#[derive(Accounts)]
+pub struct CrankPayment<'info> {
+    pub keeper: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: This is a PDA used as a signer
+    #[account(
+        mut,
+        seeds = [b"vaultpay_authority", config.key().as_ref(), subscription.user.key().as_ref()],
+        bump
+    )]
+    pub vaultpay_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"user_vault", config.key().as_ref(), subscription.user.key().as_ref()],
+        bump = user_vault.bump,
+    )]
+    pub user_vault: Box<Account<'info, UserVault>>,
+
+    /// CHECK: Must be the subscriber's position, see `VaultAccounts::available_balance`.
+    /// Otherwise a keeper could record a miss against a funded subscriber.
+    #[account(mut)]
+    pub yield_account: UncheckedAccount<'info>,
+
+    /// CHECK: This is checked in the CPI to mock_yield_source
+    #[account(mut)]
+    pub yield_reserve: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            vendor.key().as_ref(),
+            subscription.user.key().as_ref(),
+            subscription.seed.to_le_bytes().as_ref(),
+        ],
+        bump = subscription.bump,
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    #[account(
+        seeds = [b"vendor", config.key().as_ref(), vendor.authority.key().as_ref()],
+        bump = vendor.bump,
+    )]
+    pub vendor: Account<'info, Vendor>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = yield_account,
+    )]
+    pub yield_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = vendor.vendor_wallet,
+    )]
+    pub vendor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = yield_reserve
+    )]
+    pub reserve_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = vaultpay_authority
+    )]
+    pub vaultpay_authority_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = config.treasury_wallet,
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+    )]
+    pub keeper_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    // Required for metered subscriptions, covers the period being charged
+    #[account(
+        seeds = [
+            b"usage",
+            subscription.key().as_ref(),
+            subscription.start_time.to_le_bytes().as_ref(),
+            subscription.payments_made.to_le_bytes().as_ref(),
+        ],
+        bump = usage_report.bump,
+    )]
+    pub usage_report: Option<Box<Account<'info, UsageReport>>>,
+
+    // Required while the subscription's referrer earns a share of the platform fee
+    #[account(
+        mut,
+        constraint = subscription.referrer == Some(affiliate.key()) @ VaultPayError::InvalidAffiliate,
+    )]
+    pub affiliate: Option<Box<Account<'info, Affiliate>>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = affiliate,
+    )]
+    pub affiliate_escrow: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub yield_program: Program<'info, MockYieldSource>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CrankPayment<'info> {
+    pub fn crank_payment(
+        &mut self,
+        bumps: &CrankPaymentBumps,
+        payees: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+
+        let vault = VaultAccounts::new(
+            self.config.key(),
+            self.subscription.user,
+            bumps.vaultpay_authority,
+            &self.token_mint,
+            self.vaultpay_authority.to_account_info(),
+            self.vaultpay_authority_ata.to_account_info(),
+            self.yield_reserve.to_account_info(),
+            self.yield_account.to_account_info(),
+            self.yield_token_account.to_account_info(),
+            self.reserve_token_account.to_account_info(),
+            self.yield_program.to_account_info(),
+            self.token_program.to_account_info(),
+            self.associated_token_program.to_account_info(),
+            self.system_program.to_account_info(),
+        );
+        let payout = Payout {
+            vendor: &self.vendor,
+            vendor_token_account: self.vendor_token_account.to_account_info(),
+            payees,
+            treasury_token_account: self.treasury_token_account.to_account_info(),
+            keeper_token_account: Some(self.keeper_token_account.to_account_info()),
+            affiliate: self.affiliate.as_deref_mut(),
+            affiliate_escrow: self.affiliate_escrow.as_ref().map(|escrow| escrow.to_account_info()),
+        };
+        let usage_units = self
+            .usage_report
+            .as_ref()
+            .map(|usage_report| usage_report.units_for(&self.subscription))
+            .transpose()?;
+
+        charge_subscription(
+            &mut self.subscription,
+            &mut self.user_vault,
+            self.vendor.key(),
+            &self.config,
+            &vault,
+            payout,
+            usage_units,
+            now,
+        )
+    }
+}
@@ -0,0 +1,79 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::states::{Config, Invoice, InvoiceStatus, Vendor, MAX_INVOICE_MEMO_LEN};
+use crate::errors::VaultPayError;
+
+#[derive(Accounts)]
+#[instruction(seed: u64, user: Pubkey)]
+pub struct CreateInvoice<'info> {
+    #[account(mut)]
+    pub vendor_signer: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"vendor", config.key().as_ref(), vendor_signer.key().as_ref()],
+        bump = vendor.bump,
+    )]
+    pub vendor: Account<'info, Vendor>,
+
+    #[account(
+        init,
+        payer = vendor_signer,
+        space = 8 + Invoice::LEN,
+        seeds = [
+            b"invoice",
+            vendor.key().as_ref(),
+            user.as_ref(),
+            seed.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub invoice: Account<'info, Invoice>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreateInvoice<'info> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_invoice(
+        &mut self,
+        seed: u64,
+        user: Pubkey,
+        amount: u64,
+        due_at: i64,
+        expires_at: i64,
+        memo: String,
+        bumps: &CreateInvoiceBumps,
+    ) -> Result<()> {
+        require!(!self.config.locked, VaultPayError::ProgramPaused);
+        require!(self.vendor.is_in_good_standing(), VaultPayError::VendorNotApproved);
+        require!(amount > 0, VaultPayError::InvalidAmount);
+        require!(memo.len() <= MAX_INVOICE_MEMO_LEN, VaultPayError::InvalidMemo);
+        require!(
+            expires_at > Clock::get()?.unix_timestamp && due_at < expires_at,
+            VaultPayError::InvalidInvoiceDates
+        );
+
+        self.invoice.set_inner(Invoice {
+            vendor: self.vendor.key(),
+            user,
+            seed,
+            amount,
+            due_at,
+            expires_at,
+            paid_at: 0,
+            memo,
+            status: InvoiceStatus::Open,
+            bump: bumps.invoice,
+        });
+        msg!("Invoice created: {}", self.invoice.key());
+        Ok(())
+    }
+}
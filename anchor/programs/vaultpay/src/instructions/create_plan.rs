@@ -0,0 +1,72 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::states::{BillingMode, Config, Plan, Vendor};
+
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct CreatePlan<'info> {
+    #[account(mut)]
+    pub vendor_signer: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"vendor", config.key().as_ref(), vendor_signer.key().as_ref()],
+        bump = vendor.bump,
+    )]
+    pub vendor: Account<'info, Vendor>,
+
+    #[account(
+        init,
+        payer = vendor_signer,
+        space = 8 + Plan::LEN,
+        seeds = [b"plan", vendor.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub plan: Account<'info, Plan>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreatePlan<'info> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_plan(
+        &mut self,
+        seed: u64,
+        amount_per_payment: u64,
+        interval_seconds: i64,
+        number_of_payments: Option<u32>,
+        trial_period: i64,
+        billing_mode: BillingMode,
+        bumps: &CreatePlanBumps,
+    ) -> Result<()> {
+        billing_mode.validate()?;
+        Plan::validate_terms(
+            amount_per_payment,
+            interval_seconds,
+            number_of_payments,
+            trial_period,
+            &self.config,
+        )?;
+
+        self.plan.set_inner(Plan {
+            vendor: self.vendor.key(),
+            seed,
+            amount_per_payment,
+            interval_seconds,
+            number_of_payments,
+            trial_period,
+            billing_mode,
+            active: true,
+            bump: bumps.plan,
+        });
+        msg!("Plan created: {}", self.plan.key());
+        Ok(())
+    }
+}
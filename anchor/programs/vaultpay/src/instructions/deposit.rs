@@ -7,6 +7,7 @@ use anchor_spl::{
 use mock_yield_source::program::MockYieldSource;
 use mock_yield_source::cpi::accounts::Deposit as YieldSourceDeposit;
 use crate::states::Config;
+use crate::errors::VaultPayError;
 
 #[derive(Accounts)]
 pub struct Deposit<'info> {
@@ -15,7 +16,7 @@ pub struct Deposit<'info> {
     pub token_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
-        seeds = [b"config", token_mint.key().as_ref(), config.authority.key().as_ref()],
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
         bump = config.bump
     )]
     pub config: Account<'info, Config>,
@@ -70,6 +71,8 @@ pub struct Deposit<'info> {
 
 impl<'info> Deposit<'info> {
     pub fn deposit(&mut self, amount: u64, bumps: &DepositBumps) -> Result<()> {
+        require!(!self.config.locked, VaultPayError::ProgramPaused);
+
         // Transfer tokens from user to vaultpay_authority
         transfer_checked(
             CpiContext::new(
@@ -0,0 +1,51 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::states::{Config, Subscription, Vendor};
+use crate::errors::VaultPayError;
+
+#[derive(Accounts)]
+pub struct FreezeSubscription<'info> {
+    pub authority: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+        has_one = authority @ VaultPayError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"vendor", config.key().as_ref(), vendor.authority.key().as_ref()],
+        bump = vendor.bump,
+    )]
+    pub vendor: Account<'info, Vendor>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            vendor.key().as_ref(),
+            subscription.user.key().as_ref(),
+            subscription.seed.to_le_bytes().as_ref(),
+        ],
+        bump = subscription.bump,
+    )]
+    pub subscription: Account<'info, Subscription>,
+}
+
+impl<'info> FreezeSubscription<'info> {
+    /// Stops payments on a disputed subscription until it is unfrozen
+    pub fn freeze_subscription(&mut self) -> Result<()> {
+        self.subscription.locked = true;
+        msg!("Subscription frozen: {}", self.subscription.key());
+        Ok(())
+    }
+
+    pub fn unfreeze_subscription(&mut self) -> Result<()> {
+        self.subscription.locked = false;
+        msg!("Subscription unfrozen: {}", self.subscription.key());
+        Ok(())
+    }
+}
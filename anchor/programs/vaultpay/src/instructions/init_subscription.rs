@@ -1,17 +1,19 @@
 use anchor_lang::prelude::*;
-use crate::states::{Subscription, SubscriptionStatus, Config, Vendor};
+use crate::states::{Subscription, Config, Vendor, Plan, Affiliate};
+use crate::errors::VaultPayError;
 use anchor_spl::{
     token_interface::{Mint},
 };
 
 #[derive(Accounts)]
+#[instruction(seed: u64)]
 pub struct InitSubscription<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
     pub token_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
-        seeds = [b"config", token_mint.key().as_ref(), config.authority.key().as_ref()],
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
         bump = config.bump,
     )]
     pub config: Account<'info, Config>,
@@ -22,15 +24,33 @@ pub struct InitSubscription<'info> {
     )]
     pub vendor: Account<'info, Vendor>,
 
+    #[account(
+        seeds = [b"plan", vendor.key().as_ref(), plan.seed.to_le_bytes().as_ref()],
+        bump = plan.bump,
+    )]
+    pub plan: Account<'info, Plan>,
+
     #[account(
         init,
         payer = user,
-        space = 8 + Subscription::LEN,
-        seeds = [b"subscription", vendor.key().as_ref(), user.key().as_ref()],
+        space = 8 + Subscription::INIT_SPACE,
+        seeds = [
+            b"subscription",
+            vendor.key().as_ref(),
+            user.key().as_ref(),
+            seed.to_le_bytes().as_ref(),
+        ],
         bump,
     )]
     pub subscription: Account<'info, Subscription>,
 
+    // Optional referrer, earns a share of the platform fee on the first payments
+    #[account(
+        seeds = [b"affiliate", config.key().as_ref(), affiliate.wallet.as_ref()],
+        bump = affiliate.bump,
+    )]
+    pub affiliate: Option<Account<'info, Affiliate>>,
+
     pub system_program: Program<'info, System>,
 }
 
@@ -38,23 +58,27 @@ impl<'info> InitSubscription<'info> {
     pub fn init_subscription(
         &mut self,
         seed: u64,
-        amount_per_payment: u64,
-        number_of_payments: u8,
         start_time: i64,
+        trial_period: Option<i64>,
         bumps: &InitSubscriptionBumps,
     ) -> Result<()> {
-        self.subscription.set_inner(Subscription {
-            user: self.user.key(),
-            vendor: self.vendor.key(),
+        let mut subscription = Subscription::from_plan(
+            self.user.key(),
+            self.vendor.key(),
+            &self.vendor,
+            self.plan.key(),
+            &self.plan,
+            &self.config,
             seed,
             start_time,
-            amount_per_payment,
-            number_of_payments,
-            payments_made: 0,
-            status: SubscriptionStatus::Active,
-            locked: false,
-            bump: bumps.subscription,
-        });
+            trial_period,
+            bumps.subscription,
+        )?;
+        if let Some(affiliate) = &self.affiliate {
+            require!(affiliate.active, VaultPayError::AffiliateNotActive);
+            subscription.referrer = Some(affiliate.key());
+        }
+        self.subscription.set_inner(subscription);
         Ok(())
     }
 }
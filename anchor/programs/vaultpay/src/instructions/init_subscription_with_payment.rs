@@ -0,0 +1,206 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::states::{Affiliate, BillingMode, Subscription, Config, Vendor, Plan, UserVault};
+use crate::errors::VaultPayError;
+use crate::helper::{collect_payment, Payout, VaultAccounts};
+use mock_yield_source::program::MockYieldSource;
+
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct InitSubscriptionWithPayment<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        seeds = [b"vendor", config.key().as_ref(), vendor.authority.key().as_ref()],
+        bump = vendor.bump,
+    )]
+    pub vendor: Box<Account<'info, Vendor>>,
+
+    #[account(
+        seeds = [b"plan", vendor.key().as_ref(), plan.seed.to_le_bytes().as_ref()],
+        bump = plan.bump,
+    )]
+    pub plan: Box<Account<'info, Plan>>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Subscription::INIT_SPACE,
+        seeds = [
+            b"subscription",
+            vendor.key().as_ref(),
+            user.key().as_ref(),
+            seed.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub subscription: Box<Account<'info, Subscription>>,
+
+    /// CHECK: This is a PDA used as a signer
+    #[account(
+        mut,
+        seeds = [b"vaultpay_authority", config.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub vaultpay_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"user_vault", config.key().as_ref(), user.key().as_ref()],
+        bump = user_vault.bump,
+    )]
+    pub user_vault: Box<Account<'info, UserVault>>,
+
+    /// CHECK: This is checked in the CPI to mock_yield_source
+    #[account(mut)]
+    pub yield_account: UncheckedAccount<'info>,
+
+    /// CHECK: This is checked in the CPI to mock_yield_source
+    #[account(mut)]
+    pub yield_reserve: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = yield_account,
+    )]
+    pub yield_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = vendor.vendor_wallet,
+    )]
+    pub vendor_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = yield_reserve
+    )]
+    pub reserve_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = vaultpay_authority
+    )]
+    pub vaultpay_authority_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = config.treasury_wallet,
+    )]
+    pub treasury_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // Optional referrer, its share of the upfront charge goes to the escrow
+    #[account(
+        mut,
+        seeds = [b"affiliate", config.key().as_ref(), affiliate.wallet.as_ref()],
+        bump = affiliate.bump,
+    )]
+    pub affiliate: Option<Box<Account<'info, Affiliate>>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = affiliate,
+    )]
+    pub affiliate_escrow: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub yield_program: Program<'info, MockYieldSource>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitSubscriptionWithPayment<'info> {
+    pub fn init_subscription_with_payment(
+        &mut self,
+        seed: u64,
+        bumps: &InitSubscriptionWithPaymentBumps,
+        payees: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        // Usage is only known at the end of a period, metered plans cannot pay upfront
+        require!(
+            self.plan.billing_mode == BillingMode::Flat,
+            VaultPayError::BillingModeMismatch
+        );
+
+        // Paying upfront starts the subscription now and skips any trial
+        let now = Clock::get()?.unix_timestamp;
+        let mut subscription = Subscription::from_plan(
+            self.user.key(),
+            self.vendor.key(),
+            &self.vendor,
+            self.plan.key(),
+            &self.plan,
+            &self.config,
+            seed,
+            now,
+            Some(0),
+            bumps.subscription,
+        )?;
+        if let Some(affiliate) = &self.affiliate {
+            require!(affiliate.active, VaultPayError::AffiliateNotActive);
+            subscription.referrer = Some(affiliate.key());
+        }
+        self.subscription.set_inner(subscription);
+
+        // The subscription is only created if the first period clears
+        let amount = self.subscription.amount_per_payment;
+        let vault = VaultAccounts::new(
+            self.config.key(),
+            self.user.key(),
+            bumps.vaultpay_authority,
+            &self.token_mint,
+            self.vaultpay_authority.to_account_info(),
+            self.vaultpay_authority_ata.to_account_info(),
+            self.yield_reserve.to_account_info(),
+            self.yield_account.to_account_info(),
+            self.yield_token_account.to_account_info(),
+            self.reserve_token_account.to_account_info(),
+            self.yield_program.to_account_info(),
+            self.token_program.to_account_info(),
+            self.associated_token_program.to_account_info(),
+            self.system_program.to_account_info(),
+        );
+        let available = vault.available_balance()?;
+        require!(available >= amount, VaultPayError::InsufficientFunds);
+        self.subscription.record_spend(amount)?;
+        self.user_vault.record_spend(amount, now)?;
+
+        // The upfront charge is the first payment and earns the referrer its share
+        let referral_due = self.subscription.earns_referral(self.config.referral_payments);
+        collect_payment(
+            &vault,
+            &self.config,
+            Payout {
+                vendor: &self.vendor,
+                vendor_token_account: self.vendor_token_account.to_account_info(),
+                payees,
+                treasury_token_account: self.treasury_token_account.to_account_info(),
+                keeper_token_account: None,
+                affiliate: self.affiliate.as_deref_mut(),
+                affiliate_escrow: self.affiliate_escrow.as_ref().map(|escrow| escrow.to_account_info()),
+            },
+            amount,
+            referral_due,
+        )?;
+
+        // The first period is covered, the next charge is one interval out
+        self.subscription.record_payment(amount, now)?;
+        Ok(())
+    }
+}
@@ -8,7 +8,7 @@ use anchor_lang::solana_program::native_token::LAMPORTS_PER_SOL;
 
 use mock_yield_source::{program::MockYieldSource};
 use mock_yield_source::cpi::accounts::OpenVault;
-use crate::states::Config;
+use crate::states::{Config, UserVault};
 use crate::errors::VaultPayError;
 
 #[derive(Accounts)]
@@ -18,7 +18,7 @@ pub struct InitUser<'info> {
     pub token_mint: Box<InterfaceAccount<'info, Mint>>,
     
     #[account(
-        seeds = [b"config", token_mint.key().as_ref(), config.authority.key().as_ref()],
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
         bump = config.bump
     )]
     pub config: Account<'info, Config>,
@@ -33,6 +33,15 @@ pub struct InitUser<'info> {
     /// CHECK: This is a PDA used as a signer
     pub vaultpay_authority: UncheckedAccount<'info>,
     
+    #[account(
+        init,
+        payer = user,
+        space = 8 + UserVault::LEN,
+        seeds = [b"user_vault", config.key().as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    pub user_vault: Account<'info, UserVault>,
+
     /// CHECK: directing to yield platform
     #[account(mut)]
     pub yield_account: UncheckedAccount<'info>,
@@ -113,6 +122,16 @@ impl<'info> InitUser<'info> {
 
         let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
         mock_yield_source::cpi::open_vault(cpi_ctx)?;
+
+        // The vault starts uncapped, see set_vault_spending_cap
+        self.user_vault.set_inner(UserVault {
+            user: self.user.key(),
+            spending_limit: None,
+            spending_window: 0,
+            window_started_at: 0,
+            spent_in_window: 0,
+            bump: bumps.user_vault,
+        });
         Ok(())
     }
 }
@@ -1,10 +1,10 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
-    associated_token::AssociatedToken,
+    associated_token::{get_associated_token_address_with_program_id, AssociatedToken},
     token_interface::{Mint, TokenAccount, TokenInterface},
 };
 
-use crate::states::{Config, Vendor};
+use crate::states::{Config, Vendor, VendorStatus};
 
 #[derive(Accounts)]
 pub struct InitVendor<'info> {
@@ -13,7 +13,7 @@ pub struct InitVendor<'info> {
     pub token_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
-        seeds = [b"config", token_mint.key().as_ref(), config.authority.key().as_ref()],
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
         bump = config.bump,
     )]
     pub config: Account<'info, Config>,
@@ -45,8 +45,16 @@ impl<'info> InitVendor<'info> {
         self.vendor.set_inner( Vendor {
             authority: self.vendor_signer.key(),
             vendor_wallet: self.vendor_token_account.key(),
+            // Payouts default to the vendor signer's own token account
+            payout_wallet: get_associated_token_address_with_program_id(
+                &self.vendor_signer.key(),
+                &self.token_mint.key(),
+                &self.token_program.key(),
+            ),
             seed,
-            is_whitelisted: true,
+            // Vendors need to be approved by the config authority before taking subscriptions
+            status: VendorStatus::Pending,
+            revenue_splits: Vec::new(),
             bump: bumps.vendor,
         });
         msg!("Vendor initialized");
@@ -4,10 +4,14 @@ use anchor_spl::{
     token_interface::{Mint, TokenAccount, TokenInterface},
 };
 
-use crate::states::Config;
+use crate::states::{
+    Config, DEFAULT_CANCELLATION_RETENTION, DEFAULT_GRACE_PERIOD, DEFAULT_MAX_RETRIES,
+    DEFAULT_MIN_PRICE_NOTICE, DEFAULT_RETRY_INTERVAL,
+};
 use mock_yield_source::program::MockYieldSource;
 
 #[derive(Accounts)]
+#[instruction(seed: u64)]
 pub struct Initialize<'info> {
     #[account(mut)]
     pub owner: Signer<'info>,
@@ -25,7 +29,7 @@ pub struct Initialize<'info> {
         init,
         space = 8 + Config::INIT_SPACE,
         payer = owner,
-        seeds = [b"config", supported_token.key().as_ref(), owner.key().as_ref()],
+        seeds = [b"config", supported_token.key().as_ref(), seed.to_le_bytes().as_ref()],
         bump,
     )]
     pub config: Account<'info, Config>,
@@ -53,12 +57,28 @@ impl<'info> Initialize<'info> {
         max_subscription_duration: u64,
         bumps: &InitializeBumps,
     ) -> Result<()> {
+        Config::validate_params(
+            platform_fee,
+            min_subscription_duration,
+            max_subscription_duration,
+        )?;
+
         self.config.set_inner(Config {
             authority: self.owner.key(),
+            pending_authority: None,
             seed,
             platform_fee,
+            keeper_fee: 0,
+            referral_fee: 0,
+            referral_payments: 0,
             min_subscription_duration,
             max_subscription_duration,
+            max_pause_duration: 0,
+            cancellation_retention: DEFAULT_CANCELLATION_RETENTION,
+            grace_period: DEFAULT_GRACE_PERIOD,
+            max_retries: DEFAULT_MAX_RETRIES,
+            retry_interval: DEFAULT_RETRY_INTERVAL,
+            min_price_notice: DEFAULT_MIN_PRICE_NOTICE,
             supported_token: self.supported_token.key(),
             yield_source: self.yield_program.key(),
             treasury_wallet: self.treasury.key(),
@@ -0,0 +1,127 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::states::{Config, Invoice, InvoiceStatus, Vendor};
+use crate::errors::VaultPayError;
+
+#[derive(Accounts)]
+pub struct VoidInvoice<'info> {
+    pub vendor_signer: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"vendor", config.key().as_ref(), vendor_signer.key().as_ref()],
+        bump = vendor.bump,
+    )]
+    pub vendor: Account<'info, Vendor>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"invoice",
+            vendor.key().as_ref(),
+            invoice.user.as_ref(),
+            invoice.seed.to_le_bytes().as_ref(),
+        ],
+        bump = invoice.bump,
+        has_one = vendor,
+    )]
+    pub invoice: Account<'info, Invoice>,
+}
+
+impl<'info> VoidInvoice<'info> {
+    pub fn void_invoice(&mut self) -> Result<()> {
+        require!(
+            self.invoice.status == InvoiceStatus::Open,
+            VaultPayError::InvoiceNotOpen
+        );
+        self.invoice.status = InvoiceStatus::Void;
+        msg!("Invoice voided: {}", self.invoice.key());
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ExpireInvoice<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"invoice",
+            invoice.vendor.as_ref(),
+            invoice.user.as_ref(),
+            invoice.seed.to_le_bytes().as_ref(),
+        ],
+        bump = invoice.bump,
+    )]
+    pub invoice: Account<'info, Invoice>,
+}
+
+impl<'info> ExpireInvoice<'info> {
+    /// Anyone can mark an unpaid invoice as expired once its window has passed
+    pub fn expire_invoice(&mut self) -> Result<()> {
+        require!(
+            self.invoice.status == InvoiceStatus::Open,
+            VaultPayError::InvoiceNotOpen
+        );
+        require!(
+            self.invoice.is_expired(Clock::get()?.unix_timestamp),
+            VaultPayError::InvoiceNotExpired
+        );
+        self.invoice.status = InvoiceStatus::Expired;
+        msg!("Invoice expired: {}", self.invoice.key());
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct CloseInvoice<'info> {
+    #[account(mut)]
+    pub vendor_signer: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"vendor", config.key().as_ref(), vendor_signer.key().as_ref()],
+        bump = vendor.bump,
+    )]
+    pub vendor: Account<'info, Vendor>,
+
+    #[account(
+        mut,
+        close = vendor_signer,
+        seeds = [
+            b"invoice",
+            vendor.key().as_ref(),
+            invoice.user.as_ref(),
+            invoice.seed.to_le_bytes().as_ref(),
+        ],
+        bump = invoice.bump,
+        has_one = vendor,
+    )]
+    pub invoice: Account<'info, Invoice>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CloseInvoice<'info> {
+    /// Returns the rent of a settled invoice to the vendor that paid it
+    pub fn close_invoice(&mut self) -> Result<()> {
+        require!(
+            self.invoice.status != InvoiceStatus::Open,
+            VaultPayError::InvoiceStillOpen
+        );
+        msg!("Invoice closed: {}", self.invoice.key());
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::states::{Config, Plan, Vendor};
+use crate::errors::VaultPayError;
+
+#[derive(Accounts)]
+pub struct ManagePlan<'info> {
+    pub vendor_signer: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"vendor", config.key().as_ref(), vendor_signer.key().as_ref()],
+        bump = vendor.bump,
+    )]
+    pub vendor: Account<'info, Vendor>,
+
+    #[account(
+        mut,
+        seeds = [b"plan", vendor.key().as_ref(), plan.seed.to_le_bytes().as_ref()],
+        bump = plan.bump,
+    )]
+    pub plan: Account<'info, Plan>,
+}
+
+impl<'info> ManagePlan<'info> {
+    /// Changes the terms offered to new subscribers, existing subscriptions keep theirs
+    pub fn update_plan(
+        &mut self,
+        amount_per_payment: u64,
+        interval_seconds: i64,
+        number_of_payments: Option<u32>,
+        trial_period: i64,
+    ) -> Result<()> {
+        require!(self.plan.active, VaultPayError::PlanNotActive);
+        Plan::validate_terms(
+            amount_per_payment,
+            interval_seconds,
+            number_of_payments,
+            trial_period,
+            &self.config,
+        )?;
+
+        self.plan.amount_per_payment = amount_per_payment;
+        self.plan.interval_seconds = interval_seconds;
+        self.plan.number_of_payments = number_of_payments;
+        self.plan.trial_period = trial_period;
+        msg!("Plan updated: {}", self.plan.key());
+        Ok(())
+    }
+
+    /// Stops new subscriptions to the plan, existing subscriptions keep running
+    pub fn retire_plan(&mut self) -> Result<()> {
+        require!(self.plan.active, VaultPayError::PlanNotActive);
+        self.plan.active = false;
+        msg!("Plan retired: {}", self.plan.key());
+        Ok(())
+    }
+}
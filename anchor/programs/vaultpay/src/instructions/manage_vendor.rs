@@ -0,0 +1,58 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::states::{Config, Vendor, VendorStatus};
+use crate::errors::VaultPayError;
+
+#[derive(Accounts)]
+pub struct ManageVendor<'info> {
+    pub authority: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+        has_one = authority @ VaultPayError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"vendor", config.key().as_ref(), vendor.authority.key().as_ref()],
+        bump = vendor.bump,
+    )]
+    pub vendor: Account<'info, Vendor>,
+}
+
+impl<'info> ManageVendor<'info> {
+    pub fn approve_vendor(&mut self) -> Result<()> {
+        require!(
+            matches!(self.vendor.status, VendorStatus::Pending | VendorStatus::Suspended),
+            VaultPayError::InvalidVendorStatus
+        );
+        self.vendor.status = VendorStatus::Approved;
+        msg!("Vendor approved: {}", self.vendor.key());
+        Ok(())
+    }
+
+    pub fn suspend_vendor(&mut self) -> Result<()> {
+        require!(
+            self.vendor.status == VendorStatus::Approved,
+            VaultPayError::InvalidVendorStatus
+        );
+        self.vendor.status = VendorStatus::Suspended;
+        msg!("Vendor suspended: {}", self.vendor.key());
+        Ok(())
+    }
+
+    pub fn revoke_vendor(&mut self) -> Result<()> {
+        // Revocation is final, a revoked vendor cannot be approved again
+        require!(
+            self.vendor.status != VendorStatus::Revoked,
+            VaultPayError::InvalidVendorStatus
+        );
+        self.vendor.status = VendorStatus::Revoked;
+        msg!("Vendor revoked: {}", self.vendor.key());
+        Ok(())
+    }
+}
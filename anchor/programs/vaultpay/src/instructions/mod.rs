@@ -7,6 +7,32 @@ pub mod init_subscription;
 pub mod cancel_subscription;
 pub mod process_payment;
 pub mod claim_treasury;
+pub mod manage_vendor;
+pub mod update_config;
+pub mod transfer_authority;
+pub mod pause;
+pub mod freeze_subscription;
+pub mod vendor_withdraw;
+pub mod set_payout_wallet;
+pub mod create_plan;
+pub mod manage_plan;
+pub mod pause_subscription;
+pub mod acknowledge_cancellation;
+pub mod close_subscription;
+pub mod vendor_cancel_subscription;
+pub mod crank_payment;
+pub mod process_payments_batch;
+pub mod init_subscription_with_payment;
+pub mod change_plan;
+pub mod price_change;
+pub mod spending_caps;
+pub mod report_usage;
+pub mod stream;
+pub mod create_invoice;
+pub mod pay_invoice;
+pub mod manage_invoice;
+pub mod set_revenue_splits;
+pub mod affiliate;
 
 pub use initialize::*;
 pub use init_user::*;
@@ -16,4 +42,30 @@ pub use withdraw::*;
 pub use init_subscription::*;
 pub use cancel_subscription::*;
 pub use process_payment::*;
-pub use claim_treasury::*;
\ No newline at end of file
+pub use claim_treasury::*;
+pub use manage_vendor::*;
+pub use update_config::*;
+pub use transfer_authority::*;
+pub use pause::*;
+pub use freeze_subscription::*;
+pub use vendor_withdraw::*;
+pub use set_payout_wallet::*;
+pub use create_plan::*;
+pub use manage_plan::*;
+pub use pause_subscription::*;
+pub use acknowledge_cancellation::*;
+pub use close_subscription::*;
+pub use vendor_cancel_subscription::*;
+pub use crank_payment::*;
+pub use process_payments_batch::*;
+pub use init_subscription_with_payment::*;
+pub use change_plan::*;
+pub use price_change::*;
+pub use spending_caps::*;
+pub use report_usage::*;
+pub use stream::*;
+pub use create_invoice::*;
+pub use pay_invoice::*;
+pub use manage_invoice::*;
+pub use set_revenue_splits::*;
+pub use affiliate::*;
\ No newline at end of file
@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::states::Config;
+use crate::errors::VaultPayError;
+
+#[derive(Accounts)]
+pub struct SetPause<'info> {
+    pub authority: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+        has_one = authority @ VaultPayError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> SetPause<'info> {
+    /// Blocks deposits, new subscriptions and payments. Withdrawals stay open
+    pub fn pause(&mut self) -> Result<()> {
+        self.config.locked = true;
+        msg!("VaultPay paused");
+        Ok(())
+    }
+
+    pub fn unpause(&mut self) -> Result<()> {
+        self.config.locked = false;
+        msg!("VaultPay unpaused");
+        Ok(())
+    }
+}
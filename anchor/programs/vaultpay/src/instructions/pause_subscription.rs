@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::states::{Config, Subscription, SubscriptionStatus, Vendor};
+use crate::errors::VaultPayError;
+
+#[derive(Accounts)]
+pub struct PauseSubscription<'info> {
+    pub user: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"vendor", config.key().as_ref(), vendor.authority.key().as_ref()],
+        bump = vendor.bump,
+    )]
+    pub vendor: Account<'info, Vendor>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            vendor.key().as_ref(),
+            user.key().as_ref(),
+            subscription.seed.to_le_bytes().as_ref(),
+        ],
+        bump = subscription.bump,
+        has_one = user,
+    )]
+    pub subscription: Account<'info, Subscription>,
+}
+
+impl<'info> PauseSubscription<'info> {
+    pub fn pause_subscription(&mut self) -> Result<()> {
+        require!(
+            self.subscription.status == SubscriptionStatus::Active,
+            VaultPayError::SubscriptionNotActive
+        );
+        self.subscription.status = SubscriptionStatus::Paused;
+        self.subscription.paused_at = Clock::get()?.unix_timestamp;
+        msg!("Subscription paused: {}", self.subscription.key());
+        Ok(())
+    }
+
+    pub fn resume_subscription(&mut self) -> Result<()> {
+        require!(
+            self.subscription.status == SubscriptionStatus::Paused,
+            VaultPayError::SubscriptionNotPaused
+        );
+        let now = Clock::get()?.unix_timestamp;
+        self.subscription.resume(now, self.config.max_pause_duration)?;
+        msg!("Subscription resumed, next payment due: {}", self.subscription.next_payment_due);
+        Ok(())
+    }
+}
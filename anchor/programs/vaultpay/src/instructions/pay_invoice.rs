@@ -0,0 +1,164 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::states::{Config, Invoice, InvoiceStatus, UserVault, Vendor};
+use crate::errors::VaultPayError;
+use crate::helper::{collect_payment, Payout, VaultAccounts};
+use mock_yield_source::program::MockYieldSource;
+
+#[derive(Accounts)]
+pub struct PayInvoice<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        seeds = [b"vendor", config.key().as_ref(), vendor.authority.key().as_ref()],
+        bump = vendor.bump,
+    )]
+    pub vendor: Box<Account<'info, Vendor>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"invoice",
+            vendor.key().as_ref(),
+            user.key().as_ref(),
+            invoice.seed.to_le_bytes().as_ref(),
+        ],
+        bump = invoice.bump,
+        has_one = vendor,
+        has_one = user,
+    )]
+    pub invoice: Box<Account<'info, Invoice>>,
+
+    /// CHECK: This is a PDA used as a signer
+    #[account(
+        mut,
+        seeds = [b"vaultpay_authority", config.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub vaultpay_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"user_vault", config.key().as_ref(), user.key().as_ref()],
+        bump = user_vault.bump,
+    )]
+    pub user_vault: Box<Account<'info, UserVault>>,
+
+    /// CHECK: This is checked in the CPI to mock_yield_source
+    #[account(mut)]
+    pub yield_account: UncheckedAccount<'info>,
+
+    /// CHECK: This is checked in the CPI to mock_yield_source
+    #[account(mut)]
+    pub yield_reserve: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = yield_account,
+    )]
+    pub yield_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = vendor.vendor_wallet,
+    )]
+    pub vendor_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = yield_reserve
+    )]
+    pub reserve_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = vaultpay_authority
+    )]
+    pub vaultpay_authority_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = config.treasury_wallet,
+    )]
+    pub treasury_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub yield_program: Program<'info, MockYieldSource>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> PayInvoice<'info> {
+    pub fn pay_invoice(
+        &mut self,
+        bumps: &PayInvoiceBumps,
+        payees: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(!self.config.locked, VaultPayError::ProgramPaused);
+        require!(self.vendor.is_in_good_standing(), VaultPayError::VendorNotApproved);
+        require!(
+            self.invoice.status == InvoiceStatus::Open,
+            VaultPayError::InvoiceNotOpen
+        );
+        let now = Clock::get()?.unix_timestamp;
+        require!(self.invoice.is_due(now), VaultPayError::InvoiceNotDue);
+        require!(!self.invoice.is_expired(now), VaultPayError::InvoiceExpired);
+
+        let amount = self.invoice.amount;
+        let vault = VaultAccounts::new(
+            self.config.key(),
+            self.user.key(),
+            bumps.vaultpay_authority,
+            &self.token_mint,
+            self.vaultpay_authority.to_account_info(),
+            self.vaultpay_authority_ata.to_account_info(),
+            self.yield_reserve.to_account_info(),
+            self.yield_account.to_account_info(),
+            self.yield_token_account.to_account_info(),
+            self.reserve_token_account.to_account_info(),
+            self.yield_program.to_account_info(),
+            self.token_program.to_account_info(),
+            self.associated_token_program.to_account_info(),
+            self.system_program.to_account_info(),
+        );
+        let available = vault.available_balance()?;
+        require!(available >= amount, VaultPayError::InsufficientFunds);
+        self.user_vault.record_spend(amount, now)?;
+
+        collect_payment(
+            &vault,
+            &self.config,
+            Payout {
+                vendor: &self.vendor,
+                vendor_token_account: self.vendor_token_account.to_account_info(),
+                payees,
+                treasury_token_account: self.treasury_token_account.to_account_info(),
+                keeper_token_account: None,
+                affiliate: None,
+                affiliate_escrow: None,
+            },
+            amount,
+            false,
+        )?;
+
+        self.invoice.status = InvoiceStatus::Paid;
+        self.invoice.paid_at = now;
+        msg!("Invoice paid: {}", self.invoice.key());
+        Ok(())
+    }
+}
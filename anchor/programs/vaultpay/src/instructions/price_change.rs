@@ -0,0 +1,98 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::states::{BillingMode, Config, Subscription, Vendor};
+use crate::errors::VaultPayError;
+
+#[derive(Accounts)]
+pub struct ProposePriceChange<'info> {
+    pub vendor_signer: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"vendor", config.key().as_ref(), vendor_signer.key().as_ref()],
+        bump = vendor.bump,
+    )]
+    pub vendor: Account<'info, Vendor>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            vendor.key().as_ref(),
+            subscription.user.key().as_ref(),
+            subscription.seed.to_le_bytes().as_ref(),
+        ],
+        bump = subscription.bump,
+        has_one = vendor,
+    )]
+    pub subscription: Account<'info, Subscription>,
+}
+
+impl<'info> ProposePriceChange<'info> {
+    pub fn propose_price_change(&mut self, new_amount: u64, effective_at: i64) -> Result<()> {
+        require!(!self.config.locked, VaultPayError::ProgramPaused);
+        require!(self.subscription.is_open(), VaultPayError::SubscriptionNotActive);
+        require!(
+            self.subscription.billing_mode == BillingMode::Flat,
+            VaultPayError::BillingModeMismatch
+        );
+        require!(new_amount > 0, VaultPayError::InvalidAmount);
+
+        // Users get at least a full billing period of notice to accept or leave
+        let notice = self.config.min_price_notice.max(self.subscription.interval_seconds);
+        let earliest = Clock::get()?
+            .unix_timestamp
+            .checked_add(notice)
+            .ok_or(VaultPayError::MathOverflow)?;
+        require!(effective_at >= earliest, VaultPayError::InvalidEffectiveDate);
+
+        // Proposing again replaces any previous change and needs a fresh acceptance
+        self.subscription.pending_amount = Some(new_amount);
+        self.subscription.price_change_effective_at = effective_at;
+        self.subscription.price_change_accepted = false;
+        msg!("Price change to {} proposed, effective at {}", new_amount, effective_at);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct AcceptPriceChange<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            subscription.vendor.key().as_ref(),
+            user.key().as_ref(),
+            subscription.seed.to_le_bytes().as_ref(),
+        ],
+        bump = subscription.bump,
+        has_one = user,
+    )]
+    pub subscription: Account<'info, Subscription>,
+}
+
+impl<'info> AcceptPriceChange<'info> {
+    /// The new amount is charged from the effective date onwards
+    pub fn accept_price_change(&mut self) -> Result<()> {
+        require!(
+            self.subscription.pending_amount.is_some(),
+            VaultPayError::NoPendingPriceChange
+        );
+        require!(
+            Clock::get()?.unix_timestamp < self.subscription.price_change_effective_at,
+            VaultPayError::PriceChangeExpired
+        );
+        self.subscription.price_change_accepted = true;
+        msg!("Price change accepted: {}", self.subscription.key());
+        Ok(())
+    }
+}
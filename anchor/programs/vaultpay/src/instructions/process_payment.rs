@@ -1,11 +1,12 @@
 // instructions/process_payment.rs
 use anchor_lang::prelude::*;
 use anchor_spl::{    associated_token::AssociatedToken,
-    token_interface::{Mint, TokenAccount, TokenInterface, transfer_checked, TransferChecked}};
+    token_interface::{Mint, TokenAccount, TokenInterface}};
 
-use crate::states::{Config, Subscription, Vendor, SubscriptionStatus};
+use crate::states::{Affiliate, Config, Subscription, Vendor, UsageReport, UserVault};
+use crate::errors::VaultPayError;
+use crate::helper::{charge_subscription, Payout, VaultAccounts};
 use mock_yield_source::program::MockYieldSource;
-use mock_yield_source::cpi::accounts::Withdraw as YieldSourceWithdraw;
 
 #[derive(Accounts)]
 pub struct ProcessPayment<'info> {
@@ -14,7 +15,7 @@ pub struct ProcessPayment<'info> {
     pub token_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
-        seeds = [b"config", token_mint.key().as_ref(), config.authority.key().as_ref()],
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
         bump = config.bump,
     )]
     pub config: Account<'info, Config>,
@@ -27,7 +28,14 @@ pub struct ProcessPayment<'info> {
     )]
     pub vaultpay_authority: UncheckedAccount<'info>,
 
-    /// CHECK: This is checked in the CPI to mock_yield_source
+    #[account(
+        mut,
+        seeds = [b"user_vault", config.key().as_ref(), subscription.user.key().as_ref()],
+        bump = user_vault.bump,
+    )]
+    pub user_vault: Box<Account<'info, UserVault>>,
+
+    /// CHECK: Must be the subscriber's position, see `VaultAccounts::available_balance`
     #[account(mut)]
     pub yield_account: UncheckedAccount<'info>,
     
@@ -37,7 +45,12 @@ pub struct ProcessPayment<'info> {
 
     #[account(
         mut,
-        seeds = [b"subscription", vendor.key().as_ref(), subscription.user.key().as_ref()],
+        seeds = [
+            b"subscription",
+            vendor.key().as_ref(),
+            subscription.user.key().as_ref(),
+            subscription.seed.to_le_bytes().as_ref(),
+        ],
         bump = subscription.bump,
     )]
     pub subscription: Account<'info, Subscription>,
@@ -57,8 +70,7 @@ pub struct ProcessPayment<'info> {
 
     #[account(
         mut,
-        associated_token::mint = token_mint,
-        associated_token::authority = vendor_signer,
+        address = vendor.vendor_wallet,
     )]
     pub vendor_token_account: InterfaceAccount<'info, TokenAccount>,
 
@@ -78,11 +90,36 @@ pub struct ProcessPayment<'info> {
 
     #[account(
         mut,
-        associated_token::mint = token_mint,
-        associated_token::authority = config,
+        address = config.treasury_wallet,
     )]
     pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
 
+    // Required for metered subscriptions, covers the period being charged
+    #[account(
+        seeds = [
+            b"usage",
+            subscription.key().as_ref(),
+            subscription.start_time.to_le_bytes().as_ref(),
+            subscription.payments_made.to_le_bytes().as_ref(),
+        ],
+        bump = usage_report.bump,
+    )]
+    pub usage_report: Option<Box<Account<'info, UsageReport>>>,
+
+    // Required while the subscription's referrer earns a share of the platform fee
+    #[account(
+        mut,
+        constraint = subscription.referrer == Some(affiliate.key()) @ VaultPayError::InvalidAffiliate,
+    )]
+    pub affiliate: Option<Box<Account<'info, Affiliate>>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = affiliate,
+    )]
+    pub affiliate_escrow: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
     pub yield_program: Program<'info, MockYieldSource>,
     pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
@@ -90,100 +127,53 @@ pub struct ProcessPayment<'info> {
 }
 
 impl<'info> ProcessPayment<'info> {
-    pub fn process_payment(&mut self, bumps: &ProcessPaymentBumps) -> Result<()> {
-        if self.subscription.status != SubscriptionStatus::Active {
-            return Err(error!(crate::errors::VaultPayError::SubscriptionNotActive));
-        }
-
-        // Ensure subscription vendor is same as given account vendor
-        if self.subscription.vendor != self.vendor.key() {
-            return Err(error!(crate::errors::VaultPayError::InvalidVendor));
-        }
-
-        // Calculate platform fee and vendor amount
-        let platform_fee = self.subscription.amount_per_payment
-            .checked_mul(self.config.platform_fee as u64)
-            .ok_or_else(|| error!(crate::errors::VaultPayError::MathOverflow))?
-            .checked_div(10_000) // Assuming fee is in basis points
-            .ok_or_else(|| error!(crate::errors::VaultPayError::MathOverflow))?;
-
-        let amount_to_vendor = self.subscription
-            .amount_per_payment
-            .checked_sub(platform_fee)
-            .ok_or_else(|| error!(crate::errors::VaultPayError::MathUnderflow))?;
-
-        msg!("Platform Fee: {}", platform_fee);
-
-        let binding_config = self.config.key();
-        let binding_user_key = self.subscription.user.key();
-        let seeds = &[
-            b"vaultpay_authority",
-            binding_config.as_ref(),
-            binding_user_key.as_ref(),
-            &[bumps.vaultpay_authority],
-        ];
-        let signer_seeds = &[&seeds[..]];
-
-        let cpi_program = self.yield_program.to_account_info();
-        let cpi_accounts = YieldSourceWithdraw {
-            user: self.vaultpay_authority.to_account_info(),
-            token_mint: self.token_mint.to_account_info(),
-            user_token_account: self.vaultpay_authority_ata.to_account_info(),
-            yield_reserve: self.yield_reserve.to_account_info(),
-            yield_account: self.yield_account.to_account_info(),
-            yield_token_account: self.yield_token_account.to_account_info(),
-            reserve_token_account: self.reserve_token_account.to_account_info(),
-            token_program: self.token_program.to_account_info(),
-            system_program: self.system_program.to_account_info(),
-            associated_token_program: self.associated_token_program.to_account_info(),
+    pub fn process_payment(
+        &mut self,
+        bumps: &ProcessPaymentBumps,
+        payees: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+
+        let vault = VaultAccounts::new(
+            self.config.key(),
+            self.subscription.user,
+            bumps.vaultpay_authority,
+            &self.token_mint,
+            self.vaultpay_authority.to_account_info(),
+            self.vaultpay_authority_ata.to_account_info(),
+            self.yield_reserve.to_account_info(),
+            self.yield_account.to_account_info(),
+            self.yield_token_account.to_account_info(),
+            self.reserve_token_account.to_account_info(),
+            self.yield_program.to_account_info(),
+            self.token_program.to_account_info(),
+            self.associated_token_program.to_account_info(),
+            self.system_program.to_account_info(),
+        );
+        let payout = Payout {
+            vendor: &self.vendor,
+            vendor_token_account: self.vendor_token_account.to_account_info(),
+            payees,
+            treasury_token_account: self.treasury_token_account.to_account_info(),
+            keeper_token_account: None,
+            affiliate: self.affiliate.as_deref_mut(),
+            affiliate_escrow: self.affiliate_escrow.as_ref().map(|escrow| escrow.to_account_info()),
         };
-
-        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
-        mock_yield_source::cpi::withdraw(cpi_ctx, self.subscription.amount_per_payment)?;
-        
-        // Get the token balance of the VaultPay authority's ATA
-        let ata_balance = self.vaultpay_authority_ata.amount;
-        msg!("VaultPay Authority ATA balance: {}", ata_balance);
-        // Transfer to Vendor
-        transfer_checked(
-            CpiContext::new_with_signer(
-                self.token_program.to_account_info(),
-                TransferChecked {
-                    from: self.vaultpay_authority_ata.to_account_info(),
-                    to: self.vendor_token_account.to_account_info(),
-                    mint: self.token_mint.to_account_info(),
-                    authority: self.vaultpay_authority.to_account_info(),
-                },
-                signer_seeds,
-            ),
-            amount_to_vendor,
-            self.token_mint.decimals,
-        )?;
-
-        // Transfer platform fee to Treasury
-        transfer_checked(
-            CpiContext::new_with_signer(
-                self.token_program.to_account_info(),
-                TransferChecked {
-                    from: self.vaultpay_authority_ata.to_account_info(),
-                    to: self.treasury_token_account.to_account_info(),
-                    mint: self.token_mint.to_account_info(),
-                    authority: self.vaultpay_authority.to_account_info(),
-                },
-                signer_seeds,
-            ),
-            platform_fee,
-            self.token_mint.decimals,
-        )?;
-
-        // Update subscription
-        self.subscription.payments_made += 1;
-
-        if self.subscription.payments_made >= self.subscription.number_of_payments {
-            self.subscription.status = SubscriptionStatus::Completed;
-            msg!("Subscription status updated to Completed");
-        }
-
-        Ok(())
+        let usage_units = self
+            .usage_report
+            .as_ref()
+            .map(|usage_report| usage_report.units_for(&self.subscription))
+            .transpose()?;
+
+        charge_subscription(
+            &mut self.subscription,
+            &mut self.user_vault,
+            self.vendor.key(),
+            &self.config,
+            &vault,
+            payout,
+            usage_units,
+            now,
+        )
     }
 }
@@ -0,0 +1,263 @@
+// instructions/process_payments_batch.rs
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{get_associated_token_address_with_program_id, AssociatedToken},
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::errors::VaultPayError;
+use crate::states::{Config, Subscription, UserVault, Vendor};
+use crate::helper::{collect_payment, prepare_charge, ChargeCheck, Payout, VaultAccounts};
+use mock_yield_source::program::MockYieldSource;
+
+/// Accounts per subscription in `remaining_accounts`: subscription,
+/// vaultpay_authority, yield_account, yield_token_account, vaultpay_authority_ata,
+/// user_vault. The vendor's revenue split payees follow the last entry.
+pub const BATCH_ENTRY_ACCOUNTS: usize = 6;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum BatchPaymentResult {
+    Charged,
+    /// Not due, paused, frozen, metered or no longer active
+    Skipped,
+    /// Recorded as a failed attempt, see `Subscription::record_failed_payment`
+    InsufficientFunds,
+    /// Accounts did not match the subscription
+    Invalid,
+    /// Cancelled because a price change took effect without being accepted
+    Cancelled,
+    /// Would exceed a spending cap on the subscription or the user vault
+    CapExceeded,
+}
+
+#[derive(Accounts)]
+pub struct ProcessPaymentsBatch<'info> {
+    #[account(mut)]
+    pub vendor_signer: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"vendor", config.key().as_ref(), vendor_signer.key().as_ref()],
+        bump = vendor.bump,
+    )]
+    pub vendor: Account<'info, Vendor>,
+
+    /// CHECK: This is checked in the CPI to mock_yield_source
+    #[account(mut)]
+    pub yield_reserve: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        address = vendor.vendor_wallet,
+    )]
+    pub vendor_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = yield_reserve
+    )]
+    pub reserve_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = config.treasury_wallet,
+    )]
+    pub treasury_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub yield_program: Program<'info, MockYieldSource>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ProcessPaymentsBatch<'info> {
+    pub fn process_payments_batch(
+        &mut self,
+        entry_count: u8,
+        remaining_accounts: &'info [AccountInfo<'info>],
+    ) -> Result<Vec<BatchPaymentResult>> {
+        require!(!self.config.locked, VaultPayError::ProgramPaused);
+        require!(self.vendor.is_in_good_standing(), VaultPayError::VendorNotApproved);
+
+        // Payees are passed once after the entries and shared by every charge
+        let entry_accounts = entry_count as usize * BATCH_ENTRY_ACCOUNTS;
+        require!(
+            remaining_accounts.len() == entry_accounts + self.vendor.revenue_splits.len(),
+            VaultPayError::InvalidBatchAccounts
+        );
+        let (entries, payees) = remaining_accounts.split_at(entry_accounts);
+
+        let now = Clock::get()?.unix_timestamp;
+        let mut results = Vec::with_capacity(entry_count as usize);
+
+        for entry in entries.chunks(BATCH_ENTRY_ACCOUNTS) {
+            let result = self.process_entry(entry, payees, now)?;
+            results.push(result);
+        }
+
+        msg!("Processed {} subscriptions", results.len());
+        Ok(results)
+    }
+
+    fn process_entry(
+        &self,
+        entry: &'info [AccountInfo<'info>],
+        payees: &'info [AccountInfo<'info>],
+        now: i64,
+    ) -> Result<BatchPaymentResult> {
+        let [subscription_info, vaultpay_authority, yield_account, yield_token_account, vaultpay_authority_ata, user_vault_info] =
+            entry
+        else {
+            return Err(error!(VaultPayError::InvalidBatchAccounts));
+        };
+
+        // Subscription must be a writable account of this program belonging to the vendor
+        if subscription_info.owner != &crate::ID || !subscription_info.is_writable {
+            return Ok(BatchPaymentResult::Invalid);
+        }
+        let Ok(mut subscription) = Account::<Subscription>::try_from(subscription_info) else {
+            return Ok(BatchPaymentResult::Invalid);
+        };
+        let expected_subscription = Pubkey::create_program_address(
+            &[
+                b"subscription",
+                self.vendor.key().as_ref(),
+                subscription.user.as_ref(),
+                subscription.seed.to_le_bytes().as_ref(),
+                &[subscription.bump],
+            ],
+            &crate::ID,
+        );
+        if expected_subscription != Ok(subscription_info.key()) {
+            return Ok(BatchPaymentResult::Invalid);
+        }
+
+        // Vault accounts must belong to the subscriber
+        let binding_config = self.config.key();
+        let (expected_authority, authority_bump) = Pubkey::find_program_address(
+            &[b"vaultpay_authority", binding_config.as_ref(), subscription.user.as_ref()],
+            &crate::ID,
+        );
+        let token_program_key = self.token_program.key();
+        let mint_key = self.token_mint.key();
+        if vaultpay_authority.key() != expected_authority
+            || vaultpay_authority_ata.key()
+                != get_associated_token_address_with_program_id(
+                    &expected_authority,
+                    &mint_key,
+                    &token_program_key,
+                )
+            || yield_token_account.key()
+                != get_associated_token_address_with_program_id(
+                    &yield_account.key(),
+                    &mint_key,
+                    &token_program_key,
+                )
+        {
+            return Ok(BatchPaymentResult::Invalid);
+        }
+
+        if user_vault_info.owner != &crate::ID || !user_vault_info.is_writable {
+            return Ok(BatchPaymentResult::Invalid);
+        }
+        let Ok(mut user_vault) = Account::<UserVault>::try_from(user_vault_info) else {
+            return Ok(BatchPaymentResult::Invalid);
+        };
+        let expected_user_vault = Pubkey::create_program_address(
+            &[
+                b"user_vault",
+                binding_config.as_ref(),
+                subscription.user.as_ref(),
+                &[user_vault.bump],
+            ],
+            &crate::ID,
+        );
+        if expected_user_vault != Ok(user_vault_info.key()) {
+            return Ok(BatchPaymentResult::Invalid);
+        }
+
+        // Referral shares need the affiliate accounts, see process_payment
+        if subscription.earns_referral(self.config.referral_payments) {
+            return Ok(BatchPaymentResult::Skipped);
+        }
+
+        let vault = VaultAccounts::new(
+            binding_config,
+            subscription.user,
+            authority_bump,
+            &self.token_mint,
+            vaultpay_authority.to_account_info(),
+            vaultpay_authority_ata.to_account_info(),
+            self.yield_reserve.to_account_info(),
+            yield_account.to_account_info(),
+            yield_token_account.to_account_info(),
+            self.reserve_token_account.to_account_info(),
+            self.yield_program.to_account_info(),
+            self.token_program.to_account_info(),
+            self.associated_token_program.to_account_info(),
+            self.system_program.to_account_info(),
+        );
+        // Also rejects a yield position that is not the subscriber's
+        let Ok(available) = vault.available_balance() else {
+            return Ok(BatchPaymentResult::Invalid);
+        };
+
+        // Metered subscriptions have no usage report here and are skipped, see process_payment
+        let check = prepare_charge(
+            &mut subscription,
+            &mut user_vault,
+            self.vendor.key(),
+            &self.vendor,
+            &self.config,
+            None,
+            available,
+            now,
+        );
+        let amount = match check {
+            Ok(ChargeCheck::Due(amount)) => amount,
+            Ok(ChargeCheck::InsufficientFunds) => {
+                subscription.exit(&crate::ID)?;
+                return Ok(BatchPaymentResult::InsufficientFunds);
+            }
+            Ok(ChargeCheck::Cancelled) => {
+                subscription.exit(&crate::ID)?;
+                return Ok(BatchPaymentResult::Cancelled);
+            }
+            Err(err)
+                if err == VaultPayError::SpendingCapExceeded.into()
+                    || err == VaultPayError::VaultSpendingCapExceeded.into() =>
+            {
+                return Ok(BatchPaymentResult::CapExceeded);
+            }
+            Err(_) => return Ok(BatchPaymentResult::Skipped),
+        };
+
+        collect_payment(
+            &vault,
+            &self.config,
+            Payout {
+                vendor: &self.vendor,
+                vendor_token_account: self.vendor_token_account.to_account_info(),
+                payees,
+                treasury_token_account: self.treasury_token_account.to_account_info(),
+                keeper_token_account: None,
+                affiliate: None,
+                affiliate_escrow: None,
+            },
+            amount,
+            false,
+        )?;
+
+        subscription.record_payment(amount, now)?;
+        subscription.exit(&crate::ID)?;
+        user_vault.exit(&crate::ID)?;
+        Ok(BatchPaymentResult::Charged)
+    }
+}
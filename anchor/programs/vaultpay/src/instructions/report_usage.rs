@@ -0,0 +1,82 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::states::{BillingMode, Config, Subscription, UsageReport, Vendor};
+use crate::errors::VaultPayError;
+
+#[derive(Accounts)]
+pub struct ReportUsage<'info> {
+    #[account(mut)]
+    pub vendor_signer: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"vendor", config.key().as_ref(), vendor_signer.key().as_ref()],
+        bump = vendor.bump,
+    )]
+    pub vendor: Account<'info, Vendor>,
+
+    #[account(
+        seeds = [
+            b"subscription",
+            vendor.key().as_ref(),
+            subscription.user.key().as_ref(),
+            subscription.seed.to_le_bytes().as_ref(),
+        ],
+        bump = subscription.bump,
+        has_one = vendor,
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    // One report per billing period, it cannot be amended once submitted.
+    // The start time keeps a subscription recreated at the same address clear
+    // of the reports left by the closed one.
+    #[account(
+        init,
+        payer = vendor_signer,
+        space = 8 + UsageReport::LEN,
+        seeds = [
+            b"usage",
+            subscription.key().as_ref(),
+            subscription.start_time.to_le_bytes().as_ref(),
+            subscription.payments_made.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub usage_report: Account<'info, UsageReport>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ReportUsage<'info> {
+    pub fn report_usage(&mut self, units: u64, bumps: &ReportUsageBumps) -> Result<()> {
+        require!(!self.config.locked, VaultPayError::ProgramPaused);
+        require!(self.subscription.is_open(), VaultPayError::SubscriptionNotActive);
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= self.subscription.start_time, VaultPayError::SubscriptionNotActive);
+        let BillingMode::Metered { unit_price } = self.subscription.billing_mode else {
+            return err!(VaultPayError::BillingModeMismatch);
+        };
+
+        self.usage_report.set_inner(UsageReport {
+            subscription: self.subscription.key(),
+            period: self.subscription.payments_made,
+            units,
+            unit_price,
+            reported_at: now,
+            bump: bumps.usage_report,
+        });
+        msg!(
+            "Usage reported for period {}: {} units",
+            self.subscription.payments_made,
+            units
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,44 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+
+use crate::states::{Config, Vendor};
+use crate::errors::VaultPayError;
+
+#[derive(Accounts)]
+pub struct SetPayoutWallet<'info> {
+    pub vendor_signer: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"vendor", config.key().as_ref(), vendor_signer.key().as_ref()],
+        bump = vendor.bump,
+    )]
+    pub vendor: Account<'info, Vendor>,
+
+    /// Token account that receives vendor withdrawals, e.g. a cold wallet ATA
+    #[account(
+        token::mint = token_mint,
+    )]
+    pub payout_wallet: InterfaceAccount<'info, TokenAccount>,
+}
+
+impl<'info> SetPayoutWallet<'info> {
+    pub fn set_payout_wallet(&mut self) -> Result<()> {
+        // Paying out into the vendor's own escrow would lock the funds in a loop
+        require_keys_neq!(
+            self.payout_wallet.key(),
+            self.vendor.vendor_wallet,
+            VaultPayError::InvalidPayoutWallet
+        );
+        self.vendor.payout_wallet = self.payout_wallet.key();
+        msg!("Payout wallet set to: {}", self.payout_wallet.key());
+        Ok(())
+    }
+}
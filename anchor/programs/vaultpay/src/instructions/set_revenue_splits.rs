@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+
+use crate::states::{Config, RevenueSplit, Vendor};
+use crate::errors::VaultPayError;
+
+#[derive(Accounts)]
+pub struct SetRevenueSplits<'info> {
+    #[account(mut)]
+    pub vendor_signer: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"vendor", config.key().as_ref(), vendor_signer.key().as_ref()],
+        bump = vendor.bump,
+    )]
+    pub vendor: Account<'info, Vendor>,
+}
+
+impl<'info> SetRevenueSplits<'info> {
+    /// Replaces the split table, an empty table pays everything to the vendor wallet.
+    /// The payee token accounts follow in `payees`, in table order.
+    pub fn set_revenue_splits(
+        &mut self,
+        revenue_splits: Vec<RevenueSplit>,
+        payees: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        Vendor::validate_splits(&revenue_splits)?;
+
+        // Every payee has to be able to receive the config's token, or charges would fail
+        require!(
+            payees.len() == revenue_splits.len(),
+            VaultPayError::InvalidRevenueSplitAccounts
+        );
+        let token_program = *self.token_mint.to_account_info().owner;
+        for (split, payee) in revenue_splits.iter().zip(payees) {
+            require!(
+                payee.key() == split.token_account && *payee.owner == token_program,
+                VaultPayError::InvalidRevenueSplitAccounts
+            );
+            let data = payee.try_borrow_data()?;
+            let payee_account = TokenAccount::try_deserialize(&mut &data[..])?;
+            require_keys_eq!(
+                payee_account.mint,
+                self.token_mint.key(),
+                VaultPayError::InvalidRevenueSplitAccounts
+            );
+        }
+        msg!("Revenue split across {} payees", revenue_splits.len());
+        self.vendor.revenue_splits = revenue_splits;
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::states::{BillingMode, Config, Subscription, UserVault};
+use crate::errors::VaultPayError;
+
+#[derive(Accounts)]
+pub struct SetSpendingCaps<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            subscription.vendor.key().as_ref(),
+            user.key().as_ref(),
+            subscription.seed.to_le_bytes().as_ref(),
+        ],
+        bump = subscription.bump,
+        has_one = user,
+    )]
+    pub subscription: Account<'info, Subscription>,
+}
+
+impl<'info> SetSpendingCaps<'info> {
+    /// Caps only ever limit future charges, `None` removes a cap
+    pub fn set_spending_caps(
+        &mut self,
+        max_total_spend: Option<u64>,
+        max_per_period: Option<u64>,
+    ) -> Result<()> {
+        self.subscription.max_total_spend = max_total_spend;
+        self.subscription.max_per_period = max_per_period;
+        msg!(
+            "Spending caps set, total: {:?}, per period: {:?}",
+            max_total_spend,
+            max_per_period
+        );
+        Ok(())
+    }
+
+    /// Most a metered period can be billed, whatever usage the vendor reports
+    pub fn set_usage_ceiling(&mut self, usage_ceiling: u64) -> Result<()> {
+        require!(
+            matches!(self.subscription.billing_mode, BillingMode::Metered { .. }),
+            VaultPayError::BillingModeMismatch
+        );
+        require!(usage_ceiling > 0, VaultPayError::InvalidAmount);
+        self.subscription.usage_ceiling = usage_ceiling;
+        msg!("Usage ceiling set to {}", usage_ceiling);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetVaultSpendingCap<'info> {
+    pub user: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"user_vault", config.key().as_ref(), user.key().as_ref()],
+        bump = user_vault.bump,
+        has_one = user,
+    )]
+    pub user_vault: Account<'info, UserVault>,
+}
+
+impl<'info> SetVaultSpendingCap<'info> {
+    pub fn set_vault_spending_cap(
+        &mut self,
+        spending_limit: Option<u64>,
+        spending_window: i64,
+    ) -> Result<()> {
+        if spending_limit.is_some() {
+            require!(spending_window > 0, VaultPayError::InvalidSpendingWindow);
+        }
+
+        // A new limit starts a fresh window
+        self.user_vault.spending_limit = spending_limit;
+        self.user_vault.spending_window = spending_window;
+        self.user_vault.window_started_at = Clock::get()?.unix_timestamp;
+        self.user_vault.spent_in_window = 0;
+        msg!("Vault spending limit set to {:?}", spending_limit);
+        Ok(())
+    }
+}
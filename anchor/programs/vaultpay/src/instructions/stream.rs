@@ -0,0 +1,231 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::states::{Config, Stream, UserVault};
+use crate::errors::VaultPayError;
+use crate::helper::{split_platform_fee, VaultAccounts};
+use mock_yield_source::program::MockYieldSource;
+
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct CreateStream<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Stream::LEN,
+        seeds = [b"stream", config.key().as_ref(), user.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub stream: Account<'info, Stream>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreateStream<'info> {
+    pub fn create_stream(
+        &mut self,
+        seed: u64,
+        recipient: Pubkey,
+        rate_per_second: u64,
+        bumps: &CreateStreamBumps,
+    ) -> Result<()> {
+        require!(!self.config.locked, VaultPayError::ProgramPaused);
+        require!(rate_per_second > 0, VaultPayError::InvalidAmount);
+
+        self.stream.set_inner(Stream {
+            user: self.user.key(),
+            recipient,
+            seed,
+            rate_per_second,
+            start_time: Clock::get()?.unix_timestamp,
+            stopped_at: 0,
+            total_withdrawn: 0,
+            bump: bumps.stream,
+        });
+        msg!("Stream created: {}", self.stream.key());
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct WithdrawStreamed<'info> {
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"stream",
+            config.key().as_ref(),
+            stream.user.key().as_ref(),
+            stream.seed.to_le_bytes().as_ref(),
+        ],
+        bump = stream.bump,
+        has_one = recipient @ VaultPayError::Unauthorized,
+    )]
+    pub stream: Box<Account<'info, Stream>>,
+
+    /// CHECK: This is a PDA used as a signer
+    #[account(
+        mut,
+        seeds = [b"vaultpay_authority", config.key().as_ref(), stream.user.key().as_ref()],
+        bump
+    )]
+    pub vaultpay_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"user_vault", config.key().as_ref(), stream.user.key().as_ref()],
+        bump = user_vault.bump,
+    )]
+    pub user_vault: Box<Account<'info, UserVault>>,
+
+    /// CHECK: This is checked in the CPI to mock_yield_source
+    #[account(mut)]
+    pub yield_account: UncheckedAccount<'info>,
+
+    /// CHECK: This is checked in the CPI to mock_yield_source
+    #[account(mut)]
+    pub yield_reserve: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = yield_account,
+    )]
+    pub yield_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+    )]
+    pub recipient_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = yield_reserve
+    )]
+    pub reserve_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = vaultpay_authority
+    )]
+    pub vaultpay_authority_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = config.treasury_wallet,
+    )]
+    pub treasury_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub yield_program: Program<'info, MockYieldSource>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> WithdrawStreamed<'info> {
+    pub fn withdraw_streamed(&mut self, bumps: &WithdrawStreamedBumps) -> Result<()> {
+        require!(!self.config.locked, VaultPayError::ProgramPaused);
+
+        // Whatever the vault cannot cover yet stays owed to the recipient
+        let now = Clock::get()?.unix_timestamp;
+        let withdrawable = self.stream.withdrawable(now)?;
+        require!(withdrawable > 0, VaultPayError::NothingToWithdraw);
+        let vault = VaultAccounts::new(
+            self.config.key(),
+            self.stream.user,
+            bumps.vaultpay_authority,
+            &self.token_mint,
+            self.vaultpay_authority.to_account_info(),
+            self.vaultpay_authority_ata.to_account_info(),
+            self.yield_reserve.to_account_info(),
+            self.yield_account.to_account_info(),
+            self.yield_token_account.to_account_info(),
+            self.reserve_token_account.to_account_info(),
+            self.yield_program.to_account_info(),
+            self.token_program.to_account_info(),
+            self.associated_token_program.to_account_info(),
+            self.system_program.to_account_info(),
+        );
+        let amount = withdrawable.min(vault.available_balance()?);
+        require!(amount > 0, VaultPayError::InsufficientFunds);
+        self.user_vault.record_spend(amount, now)?;
+
+        let (platform_fee, amount_to_recipient) =
+            split_platform_fee(amount, self.config.platform_fee)?;
+        msg!("Platform Fee: {}", platform_fee);
+
+        vault.withdraw(amount)?;
+
+        // Transfer to Recipient
+        vault.transfer(self.recipient_token_account.to_account_info(), amount_to_recipient)?;
+
+        // Transfer platform fee to Treasury
+        vault.transfer(self.treasury_token_account.to_account_info(), platform_fee)?;
+
+        self.stream.total_withdrawn = self.stream.total_withdrawn
+            .checked_add(amount)
+            .ok_or(VaultPayError::MathOverflow)?;
+        msg!("Streamed amount withdrawn: {}", amount);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct StopStream<'info> {
+    pub user: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"stream",
+            config.key().as_ref(),
+            user.key().as_ref(),
+            stream.seed.to_le_bytes().as_ref(),
+        ],
+        bump = stream.bump,
+        has_one = user,
+    )]
+    pub stream: Account<'info, Stream>,
+}
+
+impl<'info> StopStream<'info> {
+    /// Accrual ends right away, the recipient can still withdraw what was streamed
+    pub fn stop_stream(&mut self) -> Result<()> {
+        require!(self.stream.is_running(), VaultPayError::StreamStopped);
+        self.stream.stopped_at = Clock::get()?.unix_timestamp;
+        msg!("Stream stopped: {}", self.stream.key());
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::states::Config;
+use crate::errors::VaultPayError;
+
+#[derive(Accounts)]
+pub struct ProposeAuthority<'info> {
+    pub authority: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+        has_one = authority @ VaultPayError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> ProposeAuthority<'info> {
+    pub fn propose_authority(&mut self, new_authority: Pubkey) -> Result<()> {
+        // Proposing again replaces any previous pending authority
+        self.config.pending_authority = Some(new_authority);
+        msg!("Authority transfer proposed to: {}", new_authority);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    pub new_authority: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> AcceptAuthority<'info> {
+    pub fn accept_authority(&mut self) -> Result<()> {
+        let pending_authority = self
+            .config
+            .pending_authority
+            .ok_or(VaultPayError::NoPendingAuthority)?;
+        require_keys_eq!(
+            pending_authority,
+            self.new_authority.key(),
+            VaultPayError::Unauthorized
+        );
+
+        self.config.authority = pending_authority;
+        self.config.pending_authority = None;
+        msg!("Authority transferred to: {}", pending_authority);
+        Ok(())
+    }
+}
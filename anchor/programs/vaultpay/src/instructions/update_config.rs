@@ -0,0 +1,120 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+
+use crate::states::Config;
+use crate::errors::VaultPayError;
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub authority: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+        has_one = authority @ VaultPayError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+
+    /// New treasury to collect platform fees, left unchanged when omitted
+    #[account(
+        token::mint = token_mint,
+    )]
+    pub new_treasury: Option<InterfaceAccount<'info, TokenAccount>>,
+}
+
+/// Config fields to change, `None` leaves the current value in place
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct UpdateConfigParams {
+    pub platform_fee: Option<u16>,
+    pub keeper_fee: Option<u16>,
+    pub referral_fee: Option<u16>,
+    pub referral_payments: Option<u32>,
+    pub min_subscription_duration: Option<u64>,
+    pub max_subscription_duration: Option<u64>,
+    pub max_pause_duration: Option<i64>,
+    pub cancellation_retention: Option<i64>,
+    pub grace_period: Option<i64>,
+    pub max_retries: Option<u8>,
+    pub retry_interval: Option<i64>,
+    pub min_price_notice: Option<i64>,
+}
+
+impl<'info> UpdateConfig<'info> {
+    pub fn update_config(&mut self, params: UpdateConfigParams) -> Result<()> {
+        let platform_fee = params.platform_fee.unwrap_or(self.config.platform_fee);
+        let min_subscription_duration = params
+            .min_subscription_duration
+            .unwrap_or(self.config.min_subscription_duration);
+        let max_subscription_duration = params
+            .max_subscription_duration
+            .unwrap_or(self.config.max_subscription_duration);
+
+        Config::validate_params(
+            platform_fee,
+            min_subscription_duration,
+            max_subscription_duration,
+        )?;
+
+        self.config.platform_fee = platform_fee;
+        self.config.min_subscription_duration = min_subscription_duration;
+        self.config.max_subscription_duration = max_subscription_duration;
+
+        if let Some(keeper_fee) = params.keeper_fee {
+            require!(keeper_fee <= 10_000, VaultPayError::InvalidKeeperFee);
+            self.config.keeper_fee = keeper_fee;
+        }
+
+        if let Some(referral_fee) = params.referral_fee {
+            self.config.referral_fee = referral_fee;
+        }
+
+        // Keeper tips and referral shares both come out of the platform fee
+        require!(
+            self.config.keeper_fee as u32 + self.config.referral_fee as u32 <= 10_000,
+            VaultPayError::InvalidReferralFee
+        );
+
+        if let Some(referral_payments) = params.referral_payments {
+            self.config.referral_payments = referral_payments;
+        }
+
+        if let Some(max_pause_duration) = params.max_pause_duration {
+            require!(max_pause_duration >= 0, VaultPayError::InvalidPauseDuration);
+            self.config.max_pause_duration = max_pause_duration;
+        }
+
+        if let Some(cancellation_retention) = params.cancellation_retention {
+            require!(cancellation_retention >= 0, VaultPayError::InvalidRetentionPeriod);
+            self.config.cancellation_retention = cancellation_retention;
+        }
+
+        if let Some(grace_period) = params.grace_period {
+            require!(grace_period >= 0, VaultPayError::InvalidGracePeriod);
+            self.config.grace_period = grace_period;
+        }
+
+        if let Some(max_retries) = params.max_retries {
+            self.config.max_retries = max_retries;
+        }
+
+        if let Some(retry_interval) = params.retry_interval {
+            require!(retry_interval >= 0, VaultPayError::InvalidRetryInterval);
+            self.config.retry_interval = retry_interval;
+        }
+
+        if let Some(min_price_notice) = params.min_price_notice {
+            require!(min_price_notice >= 0, VaultPayError::InvalidPriceNotice);
+            self.config.min_price_notice = min_price_notice;
+        }
+
+        if let Some(new_treasury) = &self.new_treasury {
+            self.config.treasury_wallet = new_treasury.key();
+            msg!("Treasury updated: {}", new_treasury.key());
+        }
+
+        msg!("Config updated");
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::states::{CancellationReason, Config, Subscription, SubscriptionStatus, Vendor};
+use crate::errors::VaultPayError;
+use crate::helper::{transfer_signed, transfer_unsigned};
+
+#[derive(Accounts)]
+pub struct VendorCancelSubscription<'info> {
+    pub vendor_signer: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"vendor", config.key().as_ref(), vendor_signer.key().as_ref()],
+        bump = vendor.bump,
+    )]
+    pub vendor: Account<'info, Vendor>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            vendor.key().as_ref(),
+            subscription.user.key().as_ref(),
+            subscription.seed.to_le_bytes().as_ref(),
+        ],
+        bump = subscription.bump,
+        has_one = vendor,
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    #[account(
+        mut,
+        address = vendor.vendor_wallet,
+    )]
+    pub vendor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Receives the refund, only required when refunding
+    #[account(
+        mut,
+        token::mint = token_mint,
+        constraint = user_token_account.owner == subscription.user @ VaultPayError::InvalidRefundAccount,
+    )]
+    pub user_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Pays refunds for vendors with revenue splits, whose vendor wallet
+    /// receives no revenue. Only required when refunding such a vendor.
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = vendor_signer,
+    )]
+    pub refund_source: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> VendorCancelSubscription<'info> {
+    pub fn vendor_cancel_subscription(
+        &mut self,
+        reason: CancellationReason,
+        refund: bool,
+    ) -> Result<()> {
+        require!(
+            !matches!(
+                reason,
+                CancellationReason::UserRequested | CancellationReason::PriceChangeRejected
+            ),
+            VaultPayError::InvalidCancellationReason
+        );
+        require!(
+            self.subscription.is_open(),
+            VaultPayError::SubscriptionNotActive
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+
+        if refund {
+            // Refund the unused part of the current period out of the vendor's funds
+            let refund_amount = self.subscription.refundable_amount(now)?;
+            let user_token_account = self
+                .user_token_account
+                .as_ref()
+                .ok_or(VaultPayError::InvalidRefundAccount)?;
+
+            if self.vendor.revenue_splits.is_empty() {
+                let binding_config = self.config.key();
+                let binding_authority = self.vendor.authority;
+                let seeds = &[
+                    b"vendor",
+                    binding_config.as_ref(),
+                    binding_authority.as_ref(),
+                    &[self.vendor.bump],
+                ];
+
+                transfer_signed(
+                    self.token_program.to_account_info(),
+                    &self.token_mint,
+                    self.vendor_token_account.to_account_info(),
+                    user_token_account.to_account_info(),
+                    self.vendor.to_account_info(),
+                    &[&seeds[..]],
+                    refund_amount,
+                )?;
+            } else {
+                // Split revenue never reaches the vendor wallet, the vendor refunds from its own account
+                let refund_source = self
+                    .refund_source
+                    .as_ref()
+                    .ok_or(VaultPayError::InvalidRefundAccount)?;
+
+                transfer_unsigned(
+                    self.token_program.to_account_info(),
+                    &self.token_mint,
+                    refund_source.to_account_info(),
+                    user_token_account.to_account_info(),
+                    self.vendor_signer.to_account_info(),
+                    refund_amount,
+                )?;
+            }
+            msg!("Refunded: {}", refund_amount);
+        }
+
+        self.subscription.status = SubscriptionStatus::Cancelled;
+        self.subscription.cancelled_at = now;
+        self.subscription.cancellation_reason = Some(reason);
+        // The vendor initiated the cancellation, so it is acknowledged by definition
+        self.subscription.vendor_acknowledged = true;
+        msg!(
+            "Subscription cancelled by vendor after {} payments",
+            self.subscription.payments_made
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_interface::{Mint, TokenAccount, TokenInterface, transfer_checked, TransferChecked},
+};
+
+use crate::states::{Config, Vendor};
+use crate::errors::VaultPayError;
+
+#[derive(Accounts)]
+pub struct VendorWithdraw<'info> {
+    pub vendor_signer: Signer<'info>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"vendor", config.key().as_ref(), vendor_signer.key().as_ref()],
+        bump = vendor.bump,
+    )]
+    pub vendor: Account<'info, Vendor>,
+
+    #[account(
+        mut,
+        address = vendor.vendor_wallet,
+    )]
+    pub vendor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = vendor.payout_wallet @ VaultPayError::InvalidPayoutWallet,
+    )]
+    pub payout_wallet: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> VendorWithdraw<'info> {
+    pub fn vendor_withdraw(&mut self, amount: u64) -> Result<()> {
+        require!(amount > 0, VaultPayError::InvalidAmount);
+        require!(
+            self.vendor_token_account.amount >= amount,
+            VaultPayError::InsufficientFunds
+        );
+
+        let binding_config = self.config.key();
+        let binding_authority = self.vendor.authority;
+        let seeds = &[
+            b"vendor",
+            binding_config.as_ref(),
+            binding_authority.as_ref(),
+            &[self.vendor.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        transfer_checked(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: self.vendor_token_account.to_account_info(),
+                    to: self.payout_wallet.to_account_info(),
+                    mint: self.token_mint.to_account_info(),
+                    authority: self.vendor.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+            self.token_mint.decimals,
+        )?;
+
+        msg!("Vendor withdrew {} to {}", amount, self.payout_wallet.key());
+        Ok(())
+    }
+}
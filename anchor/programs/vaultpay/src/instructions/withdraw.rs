@@ -1,11 +1,11 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{Mint, TokenAccount, TokenInterface, transfer_checked, TransferChecked},
+    token_interface::{Mint, TokenAccount, TokenInterface},
 };
 
 use mock_yield_source::program::MockYieldSource;
-use mock_yield_source::cpi::accounts::Withdraw as YieldSourceWithdraw;
+use crate::helper::VaultAccounts;
 use crate::states::Config;
 
 #[derive(Accounts)]
@@ -15,7 +15,7 @@ pub struct Withdraw<'info> {
     pub token_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
-        seeds = [b"config", token_mint.key().as_ref(), config.authority.key().as_ref()],
+        seeds = [b"config", token_mint.key().as_ref(), config.seed.to_le_bytes().as_ref()],
         bump = config.bump
     )]
     pub config: Account<'info, Config>,
@@ -70,46 +70,25 @@ pub struct Withdraw<'info> {
 
 impl<'info> Withdraw<'info> {
     pub fn withdraw(&mut self, amount: u64, bumps: &WithdrawBumps) -> Result<()> {
-        let cpi_program = self.yield_program.to_account_info();
-        let cpi_accounts = YieldSourceWithdraw {
-            user: self.vaultpay_authority.to_account_info(),
-            token_mint: self.token_mint.to_account_info(),
-            user_token_account: self.vaultpay_authority_ata.to_account_info(),
-            yield_reserve: self.yield_reserve.to_account_info(),
-            yield_account: self.yield_account.to_account_info(),
-            yield_token_account: self.yield_token_account.to_account_info(),
-            reserve_token_account: self.reserve_token_account.to_account_info(),
-            token_program: self.token_program.to_account_info(),
-            system_program: self.system_program.to_account_info(),
-            associated_token_program: self.associated_token_program.to_account_info(),
-        };
+        let vault = VaultAccounts::new(
+            self.config.key(),
+            self.user.key(),
+            bumps.vaultpay_authority,
+            &self.token_mint,
+            self.vaultpay_authority.to_account_info(),
+            self.vaultpay_authority_ata.to_account_info(),
+            self.yield_reserve.to_account_info(),
+            self.yield_account.to_account_info(),
+            self.yield_token_account.to_account_info(),
+            self.reserve_token_account.to_account_info(),
+            self.yield_program.to_account_info(),
+            self.token_program.to_account_info(),
+            self.associated_token_program.to_account_info(),
+            self.system_program.to_account_info(),
+        );
 
-        let binding_config = self.config.key();
-        let seeds = &[
-            b"vaultpay_authority",
-            binding_config.as_ref(),
-            self.user.key.as_ref(),
-            &[bumps.vaultpay_authority],
-        ];
-        let signer_seeds = &[&seeds[..]];
-
-        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
-        mock_yield_source::cpi::withdraw(cpi_ctx, amount)?;
-
-        transfer_checked(
-            CpiContext::new_with_signer(
-                self.token_program.to_account_info(),
-                TransferChecked {
-                    from: self.vaultpay_authority_ata.to_account_info(),
-                    to: self.user_token_account.to_account_info(),
-                    mint: self.token_mint.to_account_info(),
-                    authority: self.vaultpay_authority.to_account_info(),
-                },
-                signer_seeds,
-            ),
-            amount,
-            self.token_mint.decimals,
-        )?;
+        vault.withdraw(amount)?;
+        vault.transfer(self.user_token_account.to_account_info(), amount)?;
 
         Ok(())
     }
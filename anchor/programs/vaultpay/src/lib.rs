@@ -1,9 +1,11 @@
 use anchor_lang::prelude::*;
+use states::{BillingMode, CancellationReason, RevenueSplit};
 
 pub mod instructions;
 pub use instructions::*;
 pub mod states;
 pub mod errors;
+pub mod helper;
 
 declare_id!("BpNWEQeYpjpA9w9EGXEfmHoLhb1nrYcm83wTptbHgdh5");
 
@@ -51,32 +53,309 @@ pub mod vaultpay {
     pub fn init_subscription(
         ctx: Context<InitSubscription>,
         seed: u64,
-        amount_per_payment: u64,
-        number_of_payments: u8,
         start_time: i64,
+        trial_period: Option<i64>,
+    ) -> Result<()> {
+        ctx.accounts.init_subscription(seed, start_time, trial_period, &ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn init_subscription_with_payment<'info>(
+        ctx: Context<'_, '_, 'info, 'info, InitSubscriptionWithPayment<'info>>,
+        seed: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .init_subscription_with_payment(seed, &ctx.bumps, ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn process_payment<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ProcessPayment<'info>>,
     ) -> Result<()> {
-        ctx.accounts.init_subscription(
+        ctx.accounts.process_payment(&ctx.bumps, ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn crank_payment<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CrankPayment<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.crank_payment(&ctx.bumps, ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn process_payments_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ProcessPaymentsBatch<'info>>,
+        entry_count: u8,
+    ) -> Result<Vec<BatchPaymentResult>> {
+        ctx.accounts.process_payments_batch(entry_count, ctx.remaining_accounts)
+    }
+
+    pub fn cancel_subscription(ctx: Context<CancelSubscription>) -> Result<()> {
+        ctx.accounts.cancel_subscription()?;
+        Ok(())
+    }
+
+    pub fn claim_treasury(ctx: Context<ClaimTreasury>) -> Result<()> {
+        ctx.accounts.claim_treasury()?;
+        Ok(())
+    }
+
+    pub fn approve_vendor(ctx: Context<ManageVendor>) -> Result<()> {
+        ctx.accounts.approve_vendor()?;
+        Ok(())
+    }
+
+    pub fn suspend_vendor(ctx: Context<ManageVendor>) -> Result<()> {
+        ctx.accounts.suspend_vendor()?;
+        Ok(())
+    }
+
+    pub fn revoke_vendor(ctx: Context<ManageVendor>) -> Result<()> {
+        ctx.accounts.revoke_vendor()?;
+        Ok(())
+    }
+
+    pub fn update_config(ctx: Context<UpdateConfig>, params: UpdateConfigParams) -> Result<()> {
+        ctx.accounts.update_config(params)?;
+        Ok(())
+    }
+
+    pub fn propose_authority(ctx: Context<ProposeAuthority>, new_authority: Pubkey) -> Result<()> {
+        ctx.accounts.propose_authority(new_authority)?;
+        Ok(())
+    }
+
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        ctx.accounts.accept_authority()?;
+        Ok(())
+    }
+
+    pub fn pause(ctx: Context<SetPause>) -> Result<()> {
+        ctx.accounts.pause()?;
+        Ok(())
+    }
+
+    pub fn unpause(ctx: Context<SetPause>) -> Result<()> {
+        ctx.accounts.unpause()?;
+        Ok(())
+    }
+
+    pub fn freeze_subscription(ctx: Context<FreezeSubscription>) -> Result<()> {
+        ctx.accounts.freeze_subscription()?;
+        Ok(())
+    }
+
+    pub fn unfreeze_subscription(ctx: Context<FreezeSubscription>) -> Result<()> {
+        ctx.accounts.unfreeze_subscription()?;
+        Ok(())
+    }
+
+    pub fn set_revenue_splits<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SetRevenueSplits<'info>>,
+        revenue_splits: Vec<RevenueSplit>,
+    ) -> Result<()> {
+        ctx.accounts.set_revenue_splits(revenue_splits, ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn set_payout_wallet(ctx: Context<SetPayoutWallet>) -> Result<()> {
+        ctx.accounts.set_payout_wallet()?;
+        Ok(())
+    }
+
+    pub fn vendor_withdraw(ctx: Context<VendorWithdraw>, amount: u64) -> Result<()> {
+        ctx.accounts.vendor_withdraw(amount)?;
+        Ok(())
+    }
+
+    pub fn create_plan(
+        ctx: Context<CreatePlan>,
+        seed: u64,
+        amount_per_payment: u64,
+        interval_seconds: i64,
+        number_of_payments: Option<u32>,
+        trial_period: i64,
+        billing_mode: BillingMode,
+    ) -> Result<()> {
+        ctx.accounts.create_plan(
             seed,
             amount_per_payment,
+            interval_seconds,
             number_of_payments,
-            start_time,
+            trial_period,
+            billing_mode,
             &ctx.bumps,
         )?;
         Ok(())
     }
 
-    pub fn process_payment(ctx: Context<ProcessPayment>) -> Result<()> {
-        ctx.accounts.process_payment(&ctx.bumps)?;
+    pub fn update_plan(
+        ctx: Context<ManagePlan>,
+        amount_per_payment: u64,
+        interval_seconds: i64,
+        number_of_payments: Option<u32>,
+        trial_period: i64,
+    ) -> Result<()> {
+        ctx.accounts.update_plan(
+            amount_per_payment,
+            interval_seconds,
+            number_of_payments,
+            trial_period,
+        )?;
         Ok(())
     }
 
-    pub fn cancel_subscription(ctx: Context<CancelSubscription>) -> Result<()> {
-        ctx.accounts.cancel_subscription()?;
+    pub fn retire_plan(ctx: Context<ManagePlan>) -> Result<()> {
+        ctx.accounts.retire_plan()?;
         Ok(())
     }
 
-    pub fn claim_treasury(ctx: Context<ClaimTreasury>) -> Result<()> {
-        ctx.accounts.claim_treasury()?;
+    pub fn pause_subscription(ctx: Context<PauseSubscription>) -> Result<()> {
+        ctx.accounts.pause_subscription()?;
+        Ok(())
+    }
+
+    pub fn resume_subscription(ctx: Context<PauseSubscription>) -> Result<()> {
+        ctx.accounts.resume_subscription()?;
+        Ok(())
+    }
+
+    pub fn change_plan<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ChangePlan<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.change_plan(&ctx.bumps, ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn propose_price_change(
+        ctx: Context<ProposePriceChange>,
+        new_amount: u64,
+        effective_at: i64,
+    ) -> Result<()> {
+        ctx.accounts.propose_price_change(new_amount, effective_at)?;
+        Ok(())
+    }
+
+    pub fn accept_price_change(ctx: Context<AcceptPriceChange>) -> Result<()> {
+        ctx.accounts.accept_price_change()?;
+        Ok(())
+    }
+
+    pub fn set_spending_caps(
+        ctx: Context<SetSpendingCaps>,
+        max_total_spend: Option<u64>,
+        max_per_period: Option<u64>,
+    ) -> Result<()> {
+        ctx.accounts.set_spending_caps(max_total_spend, max_per_period)?;
+        Ok(())
+    }
+
+    pub fn set_vault_spending_cap(
+        ctx: Context<SetVaultSpendingCap>,
+        spending_limit: Option<u64>,
+        spending_window: i64,
+    ) -> Result<()> {
+        ctx.accounts.set_vault_spending_cap(spending_limit, spending_window)?;
+        Ok(())
+    }
+
+    pub fn set_usage_ceiling(ctx: Context<SetSpendingCaps>, usage_ceiling: u64) -> Result<()> {
+        ctx.accounts.set_usage_ceiling(usage_ceiling)?;
+        Ok(())
+    }
+
+    pub fn report_usage(ctx: Context<ReportUsage>, units: u64) -> Result<()> {
+        ctx.accounts.report_usage(units, &ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn create_stream(
+        ctx: Context<CreateStream>,
+        seed: u64,
+        recipient: Pubkey,
+        rate_per_second: u64,
+    ) -> Result<()> {
+        ctx.accounts.create_stream(seed, recipient, rate_per_second, &ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn withdraw_streamed(ctx: Context<WithdrawStreamed>) -> Result<()> {
+        ctx.accounts.withdraw_streamed(&ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn stop_stream(ctx: Context<StopStream>) -> Result<()> {
+        ctx.accounts.stop_stream()?;
+        Ok(())
+    }
+
+    pub fn create_invoice(
+        ctx: Context<CreateInvoice>,
+        seed: u64,
+        user: Pubkey,
+        amount: u64,
+        due_at: i64,
+        expires_at: i64,
+        memo: String,
+    ) -> Result<()> {
+        ctx.accounts
+            .create_invoice(seed, user, amount, due_at, expires_at, memo, &ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn pay_invoice<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PayInvoice<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.pay_invoice(&ctx.bumps, ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn void_invoice(ctx: Context<VoidInvoice>) -> Result<()> {
+        ctx.accounts.void_invoice()?;
+        Ok(())
+    }
+
+    pub fn expire_invoice(ctx: Context<ExpireInvoice>) -> Result<()> {
+        ctx.accounts.expire_invoice()?;
+        Ok(())
+    }
+
+    pub fn close_invoice(ctx: Context<CloseInvoice>) -> Result<()> {
+        ctx.accounts.close_invoice()?;
+        Ok(())
+    }
+
+    pub fn register_affiliate(ctx: Context<RegisterAffiliate>, wallet: Pubkey) -> Result<()> {
+        ctx.accounts.register_affiliate(wallet, &ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn revoke_affiliate(ctx: Context<RevokeAffiliate>) -> Result<()> {
+        ctx.accounts.revoke_affiliate()?;
+        Ok(())
+    }
+
+    pub fn claim_affiliate_earnings(ctx: Context<ClaimAffiliateEarnings>) -> Result<()> {
+        ctx.accounts.claim_affiliate_earnings()?;
+        Ok(())
+    }
+
+    pub fn acknowledge_cancellation(ctx: Context<AcknowledgeCancellation>) -> Result<()> {
+        ctx.accounts.acknowledge_cancellation()?;
+        Ok(())
+    }
+
+    pub fn close_subscription(ctx: Context<CloseSubscription>) -> Result<()> {
+        ctx.accounts.close_subscription()?;
+        Ok(())
+    }
+
+    pub fn vendor_cancel_subscription(
+        ctx: Context<VendorCancelSubscription>,
+        reason: CancellationReason,
+        refund: bool,
+    ) -> Result<()> {
+        ctx.accounts.vendor_cancel_subscription(reason, refund)?;
         Ok(())
     }
-}
\ No newline at end of file
+}
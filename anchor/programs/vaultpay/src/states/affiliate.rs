@@ -0,0 +1,17 @@
+// states/affiliate.rs
+use anchor_lang::prelude::*;
+
+/// Growth partner registered by the config authority. Referral earnings
+/// build up in the token account owned by this PDA until claimed.
+#[account]
+pub struct Affiliate {
+    pub wallet: Pubkey,
+    pub active: bool,
+    pub total_earned: u64,
+    pub total_claimed: u64,
+    pub bump: u8,
+}
+
+impl Affiliate {
+    pub const LEN: usize = 32 + 1 + 8 + 8 + 1;
+}
@@ -1,16 +1,65 @@
 use anchor_lang::prelude::*;
 
+use crate::errors::VaultPayError;
+
+/// Hard cap on the platform fee, in basis points (10%)
+pub const MAX_PLATFORM_FEE_BPS: u16 = 1_000;
+
+/// Time a past due subscription has to recover before it gets suspended (3 days)
+pub const DEFAULT_GRACE_PERIOD: i64 = 3 * 24 * 60 * 60;
+
+/// Time a cancelled subscription is kept as a record unless the vendor acknowledges it (30 days)
+pub const DEFAULT_CANCELLATION_RETENTION: i64 = 30 * 24 * 60 * 60;
+
+/// Failed charges tolerated before a past due subscription can be suspended
+pub const DEFAULT_MAX_RETRIES: u8 = 3;
+
+/// Time a failed charge has to wait before it can be retried (1 day)
+pub const DEFAULT_RETRY_INTERVAL: i64 = 24 * 60 * 60;
+
+/// Least notice a vendor has to give before a price change takes effect (30 days)
+pub const DEFAULT_MIN_PRICE_NOTICE: i64 = 30 * 24 * 60 * 60;
+
 #[account]
 #[derive(InitSpace)]
 pub struct Config {
     pub authority: Pubkey,
+    pub pending_authority: Option<Pubkey>,
     pub seed: u64,
     pub platform_fee: u16,
+    pub keeper_fee: u16, // share of the platform fee paid to crankers, in basis points
+    pub referral_fee: u16, // share of the platform fee paid to referrers, in basis points
+    pub referral_payments: u32, // payments per subscription that earn the referrer a share
     pub min_subscription_duration: u64,
     pub max_subscription_duration: u64,
+    pub max_pause_duration: i64, // 0 means pauses are not capped
+    pub cancellation_retention: i64,
+    pub grace_period: i64,
+    pub max_retries: u8,
+    pub retry_interval: i64, // minimum time between attempts on a past due subscription
+    pub min_price_notice: i64, // a billing period of notice is required even when this is shorter
     pub supported_token: Pubkey,
     pub yield_source: Pubkey,
     pub treasury_wallet: Pubkey,
     pub locked: bool,
     pub bump: u8,
 }
+
+impl Config {
+    pub fn validate_params(
+        platform_fee: u16,
+        min_subscription_duration: u64,
+        max_subscription_duration: u64,
+    ) -> Result<()> {
+        require!(
+            platform_fee <= MAX_PLATFORM_FEE_BPS,
+            VaultPayError::PlatformFeeTooHigh
+        );
+        require!(
+            min_subscription_duration > 0
+                && min_subscription_duration <= max_subscription_duration,
+            VaultPayError::InvalidDurationBounds
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+// states/invoice.rs
+use anchor_lang::prelude::*;
+
+/// Longest memo a vendor can attach to an invoice, in bytes
+pub const MAX_INVOICE_MEMO_LEN: usize = 64;
+
+/// One-off bill from a vendor to a user, paid from the user's vault
+#[account]
+pub struct Invoice {
+    pub vendor: Pubkey,
+    pub user: Pubkey,
+    pub seed: u64,
+    pub amount: u64,
+    pub due_at: i64,
+    pub expires_at: i64,
+    pub paid_at: i64,
+    pub memo: String,
+    pub status: InvoiceStatus, // 1 byte
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum InvoiceStatus {
+    Open,
+    Paid,
+    Void,
+    Expired,
+}
+
+impl Invoice {
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 8 + 8 + 8 + (4 + MAX_INVOICE_MEMO_LEN) + 1 + 1;
+
+    /// Invoices are collected from their due date until they expire
+    pub fn is_due(&self, now: i64) -> bool {
+        now >= self.due_at
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        now >= self.expires_at
+    }
+}
@@ -1,7 +1,19 @@
 pub mod config;
 pub mod subscription;
 pub mod vendor;
+pub mod plan;
+pub mod user_vault;
+pub mod usage_report;
+pub mod stream;
+pub mod invoice;
+pub mod affiliate;
 
 pub use config::*;
 pub use subscription::*;
 pub use vendor::*;
+pub use plan::*;
+pub use user_vault::*;
+pub use usage_report::*;
+pub use stream::*;
+pub use invoice::*;
+pub use affiliate::*;
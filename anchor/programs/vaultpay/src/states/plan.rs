@@ -0,0 +1,73 @@
+// states/plan.rs
+use anchor_lang::prelude::*;
+
+use crate::errors::VaultPayError;
+use crate::states::Config;
+
+#[account]
+pub struct Plan {
+    pub vendor: Pubkey,
+    pub seed: u64,
+    pub amount_per_payment: u64,
+    pub interval_seconds: i64,
+    pub number_of_payments: Option<u32>, // None runs until cancelled
+    pub trial_period: i64,
+    pub billing_mode: BillingMode,
+    pub active: bool,
+    pub bump: u8,
+}
+
+/// How the amount of each charge is determined. Metered plans bill the
+/// usage reported by the vendor, `amount_per_payment` is then the default
+/// per-period ceiling for new subscribers.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum BillingMode {
+    Flat,
+    Metered { unit_price: u64 },
+}
+
+impl BillingMode {
+    pub fn validate(&self) -> Result<()> {
+        if let BillingMode::Metered { unit_price } = self {
+            require!(*unit_price > 0, VaultPayError::InvalidAmount);
+        }
+        Ok(())
+    }
+}
+
+impl Plan {
+    pub const LEN: usize = 32 + 8 + 8 + 8 + (1 + 4) + 8 + (1 + 8) + 1 + 1;
+
+    pub fn validate_terms(
+        amount_per_payment: u64,
+        interval_seconds: i64,
+        number_of_payments: Option<u32>,
+        trial_period: i64,
+        config: &Config,
+    ) -> Result<()> {
+        require!(amount_per_payment > 0, VaultPayError::InvalidAmount);
+        require!(interval_seconds > 0, VaultPayError::InvalidInterval);
+        require!(trial_period >= 0, VaultPayError::InvalidTrialPeriod);
+
+        // Open-ended plans run until cancelled, so the duration window does not apply
+        let Some(number_of_payments) = number_of_payments else {
+            return Ok(());
+        };
+        require!(number_of_payments > 0, VaultPayError::InvalidNumberOfPayments);
+
+        // Total duration covered by the subscription, trial included, must fit the configured window
+        let duration = (interval_seconds as u64)
+            .checked_mul(number_of_payments as u64)
+            .and_then(|duration| duration.checked_add(trial_period as u64))
+            .ok_or(VaultPayError::MathOverflow)?;
+        require!(
+            duration >= config.min_subscription_duration,
+            VaultPayError::SubscriptionTooShort
+        );
+        require!(
+            duration <= config.max_subscription_duration,
+            VaultPayError::SubscriptionTooLong
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+// states/stream.rs
+use anchor_lang::prelude::*;
+
+use crate::errors::VaultPayError;
+
+/// Continuous payment from a user's vault that accrues every second
+#[account]
+pub struct Stream {
+    pub user: Pubkey,
+    pub recipient: Pubkey,
+    pub seed: u64,
+    pub rate_per_second: u64,
+    pub start_time: i64,
+    pub stopped_at: i64, // 0 while the stream is running
+    pub total_withdrawn: u64,
+    pub bump: u8,
+}
+
+impl Stream {
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 8 + 8 + 8 + 1;
+
+    pub fn is_running(&self) -> bool {
+        self.stopped_at == 0
+    }
+
+    /// Amount streamed so far that the recipient has not withdrawn yet
+    pub fn withdrawable(&self, now: i64) -> Result<u64> {
+        let end = if self.is_running() { now } else { self.stopped_at };
+        let elapsed = end.saturating_sub(self.start_time).max(0);
+
+        let streamed = (self.rate_per_second as u128)
+            .checked_mul(elapsed as u128)
+            .ok_or(VaultPayError::MathOverflow)?;
+        let owed = streamed.saturating_sub(self.total_withdrawn as u128);
+
+        // Withdrawals are bounded by the vault anyway, saturate rather than lock the stream
+        Ok(owed.min(u64::MAX as u128) as u64)
+    }
+}
@@ -1,27 +1,356 @@
 // states/subscription.rs
 use anchor_lang::prelude::*;
 
+use crate::errors::VaultPayError;
+use crate::states::{BillingMode, Config, Plan, Vendor};
+
 #[account]
+#[derive(InitSpace)]
 pub struct Subscription {
     pub user: Pubkey,
     pub vendor: Pubkey,
+    pub plan: Pubkey,
     pub seed: u64,
     pub start_time: i64,
+    pub trial_ends_at: i64,
+    pub interval_seconds: i64,
+    pub next_payment_due: i64,
+    pub paused_at: i64,
+    pub cancelled_at: i64,
+    pub cancellation_reason: Option<CancellationReason>,
     pub amount_per_payment: u64,
-    pub number_of_payments: u8,
-    pub payments_made: u8,
-    pub status: SubscriptionStatus, // 1 byte
+    pub credit_balance: u64, // owed back to the user from plan downgrades
+    pub last_payment_amount: u64, // what the user actually paid for the current period
+    pub pending_amount: Option<u64>,
+    pub price_change_effective_at: i64,
+    pub price_change_accepted: bool,
+    pub max_total_spend: Option<u64>,
+    pub max_per_period: Option<u64>,
+    pub total_spent: u64,
+    pub billing_mode: BillingMode,
+    pub usage_ceiling: u64, // most a metered period can be billed, set by the user
+    pub referrer: Option<Pubkey>, // affiliate that brought the subscriber in
+    pub number_of_payments: Option<u32>, // None runs until cancelled
+    pub payments_made: u32,
+    pub failed_attempts: u8,
+    pub last_attempt_at: i64,
+    pub status: SubscriptionStatus,
     pub locked: bool,
+    pub vendor_acknowledged: bool,
     pub bump: u8,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
 pub enum SubscriptionStatus {
     Active,
+    Paused,
+    PastDue,
+    Suspended,
     Cancelled,
     Completed,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum CancellationReason {
+    UserRequested,
+    ServiceDiscontinued,
+    TermsViolation,
+    Other,
+    PriceChangeRejected,
+}
+
 impl Subscription {
-    pub const LEN: usize = 32 + 32 + 8 + 8 + 8 + 1 + 1 + 1 + 1 + 1;
+    /// Builds a new subscription from a vendor plan. Subscribers may shorten
+    /// or skip the plan's trial, but never extend it.
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_plan(
+        user: Pubkey,
+        vendor_key: Pubkey,
+        vendor: &Vendor,
+        plan_key: Pubkey,
+        plan: &Plan,
+        config: &Config,
+        seed: u64,
+        start_time: i64,
+        trial_period: Option<i64>,
+        bump: u8,
+    ) -> Result<Self> {
+        require!(!config.locked, VaultPayError::ProgramPaused);
+        require!(vendor.is_in_good_standing(), VaultPayError::VendorNotApproved);
+        require!(plan.active, VaultPayError::PlanNotActive);
+        require!(
+            start_time >= Clock::get()?.unix_timestamp,
+            VaultPayError::StartTimeInPast
+        );
+
+        let trial_period = trial_period.unwrap_or(plan.trial_period);
+        require!(
+            (0..=plan.trial_period).contains(&trial_period),
+            VaultPayError::InvalidTrialPeriod
+        );
+
+        // Bounds may have changed since the plan was published
+        Plan::validate_terms(
+            plan.amount_per_payment,
+            plan.interval_seconds,
+            plan.number_of_payments,
+            trial_period,
+            config,
+        )?;
+
+        // The first payment is due once the trial, if any, is over
+        let trial_ends_at = start_time
+            .checked_add(trial_period)
+            .ok_or(VaultPayError::MathOverflow)?;
+
+        Ok(Self {
+            user,
+            vendor: vendor_key,
+            plan: plan_key,
+            seed,
+            start_time,
+            trial_ends_at,
+            interval_seconds: plan.interval_seconds,
+            next_payment_due: trial_ends_at,
+            paused_at: 0,
+            cancelled_at: 0,
+            cancellation_reason: None,
+            amount_per_payment: plan.amount_per_payment,
+            credit_balance: 0,
+            last_payment_amount: 0,
+            pending_amount: None,
+            price_change_effective_at: 0,
+            price_change_accepted: false,
+            max_total_spend: None,
+            max_per_period: None,
+            total_spent: 0,
+            billing_mode: plan.billing_mode,
+            usage_ceiling: plan.amount_per_payment,
+            referrer: None,
+            number_of_payments: plan.number_of_payments,
+            payments_made: 0,
+            failed_attempts: 0,
+            last_attempt_at: 0,
+            status: SubscriptionStatus::Active,
+            locked: false,
+            vendor_acknowledged: false,
+            bump,
+        })
+    }
+
+    pub fn is_fully_paid(&self) -> bool {
+        self.number_of_payments
+            .is_some_and(|number_of_payments| self.payments_made >= number_of_payments)
+    }
+
+    /// Whether the next charge still pays the referrer a share of the platform fee
+    pub fn earns_referral(&self, referral_payments: u32) -> bool {
+        self.referrer.is_some() && self.payments_made < referral_payments
+    }
+
+    pub fn in_trial(&self, now: i64) -> bool {
+        now < self.trial_ends_at
+    }
+
+    /// Whether the subscription can still be charged or cancelled
+    pub fn is_open(&self) -> bool {
+        !matches!(
+            self.status,
+            SubscriptionStatus::Cancelled | SubscriptionStatus::Completed
+        )
+    }
+
+    /// Whether a charge may be attempted, past due subscriptions keep being retried
+    pub fn is_chargeable(&self) -> bool {
+        matches!(
+            self.status,
+            SubscriptionStatus::Active | SubscriptionStatus::PastDue
+        )
+    }
+
+    /// Amount to collect for the next period once any credit is applied
+    pub fn amount_due(&self) -> u64 {
+        self.amount_per_payment.saturating_sub(self.credit_balance)
+    }
+
+    /// Bills the usage reported for the current period of a metered
+    /// subscription, capped at the ceiling the user agreed to
+    pub fn apply_usage(&mut self, units: u64) -> Result<()> {
+        let BillingMode::Metered { unit_price } = self.billing_mode else {
+            return err!(VaultPayError::BillingModeMismatch);
+        };
+        let amount = units
+            .checked_mul(unit_price)
+            .ok_or(VaultPayError::MathOverflow)?;
+        self.amount_per_payment = amount.min(self.usage_ceiling);
+        Ok(())
+    }
+
+    /// Books a charge against the caps the user set on this subscription
+    pub fn record_spend(&mut self, amount: u64) -> Result<()> {
+        let total_spent = self.total_spent
+            .checked_add(amount)
+            .ok_or(VaultPayError::MathOverflow)?;
+        require!(
+            self.max_per_period.map_or(true, |cap| amount <= cap)
+                && self.max_total_spend.map_or(true, |cap| total_spent <= cap),
+            VaultPayError::SpendingCapExceeded
+        );
+        self.total_spent = total_spent;
+        Ok(())
+    }
+
+    /// Books a successful charge of `amount` and moves the schedule to the next period
+    pub fn record_payment(&mut self, amount: u64, now: i64) -> Result<()> {
+        self.credit_balance = self.credit_balance.saturating_sub(self.amount_per_payment);
+        self.last_payment_amount = amount;
+        self.payments_made = self.payments_made
+            .checked_add(1)
+            .ok_or(VaultPayError::MathOverflow)?;
+        self.next_payment_due = self.next_payment_due
+            .checked_add(self.interval_seconds)
+            .ok_or(VaultPayError::MathOverflow)?;
+        self.failed_attempts = 0;
+        self.last_attempt_at = now;
+        self.status = if self.is_fully_paid() {
+            SubscriptionStatus::Completed
+        } else {
+            SubscriptionStatus::Active
+        };
+        Ok(())
+    }
+
+    /// Books a charge that could not be covered. The subscription is past due
+    /// until it either recovers or runs out of retries after the grace period
+    pub fn record_failed_payment(&mut self, now: i64, grace_period: i64, max_retries: u8) {
+        self.failed_attempts = self.failed_attempts.saturating_add(1);
+        self.last_attempt_at = now;
+
+        let grace_ends_at = self.next_payment_due.saturating_add(grace_period);
+        self.status = if self.failed_attempts >= max_retries && now >= grace_ends_at {
+            SubscriptionStatus::Suspended
+        } else {
+            SubscriptionStatus::PastDue
+        };
+    }
+
+    /// Reactivates a paused subscription and pushes the schedule back by the
+    /// time spent paused, capped at `max_pause_duration` when it is non-zero
+    pub fn resume(&mut self, now: i64, max_pause_duration: i64) -> Result<()> {
+        let mut paused_for = now
+            .checked_sub(self.paused_at)
+            .ok_or(VaultPayError::MathUnderflow)?;
+        if max_pause_duration > 0 {
+            paused_for = paused_for.min(max_pause_duration);
+        }
+
+        self.next_payment_due = self.next_payment_due
+            .checked_add(paused_for)
+            .ok_or(VaultPayError::MathOverflow)?;
+        self.paused_at = 0;
+        self.status = SubscriptionStatus::Active;
+        Ok(())
+    }
+
+    /// Price of the rest of the current period, used to prorate plan changes
+    pub fn unused_period_amount(&self, now: i64) -> Result<u64> {
+        self.unused_share(self.amount_per_payment, now)
+    }
+
+    /// Part of the last charge covering the rest of the current period. Credits
+    /// and metered usage mean this can differ from the period's price.
+    pub fn refundable_amount(&self, now: i64) -> Result<u64> {
+        self.unused_share(self.last_payment_amount, now)
+    }
+
+    fn unused_share(&self, amount: u64, now: i64) -> Result<u64> {
+        if self.payments_made == 0 {
+            return Ok(0);
+        }
+
+        // Time stops counting while the subscription is paused
+        let reference = if self.status == SubscriptionStatus::Paused {
+            self.paused_at
+        } else {
+            now
+        };
+        let remaining = self.next_payment_due
+            .saturating_sub(reference)
+            .clamp(0, self.interval_seconds);
+
+        let amount = (amount as u128)
+            .checked_mul(remaining as u128)
+            .ok_or(VaultPayError::MathOverflow)?
+            .checked_div(self.interval_seconds as u128)
+            .ok_or(VaultPayError::MathOverflow)?;
+        Ok(amount as u64)
+    }
+
+    /// Moves the subscription onto new plan terms and prorates the rest of the
+    /// current period. Returns the amount to charge now, a cheaper plan is
+    /// credited against later charges instead.
+    pub fn switch_plan(&mut self, plan_key: Pubkey, plan: &Plan, now: i64) -> Result<u64> {
+        let old_unused = self.unused_period_amount(now)?;
+
+        self.plan = plan_key;
+        self.number_of_payments = plan.number_of_payments;
+        self.billing_mode = plan.billing_mode;
+        self.clear_price_change();
+
+        // Metered periods are billed on reported usage, nothing to prorate
+        if let BillingMode::Metered { .. } = plan.billing_mode {
+            return Ok(0);
+        }
+
+        self.amount_per_payment = plan.amount_per_payment;
+
+        let new_unused = self.unused_period_amount(now)?;
+        if new_unused >= old_unused {
+            // Credit left from earlier downgrades goes toward the upgrade first
+            let difference = new_unused - old_unused;
+            let credit_used = difference.min(self.credit_balance);
+            self.credit_balance -= credit_used;
+            return Ok(difference - credit_used);
+        }
+
+        self.credit_balance = self.credit_balance
+            .checked_add(old_unused - new_unused)
+            .ok_or(VaultPayError::MathOverflow)?;
+        Ok(0)
+    }
+
+    /// Applies a pending price change once it takes effect. Price cuts apply
+    /// without the user's consent. Returns true when the user never accepted
+    /// a rise and the subscription was cancelled instead
+    pub fn settle_price_change(&mut self, now: i64) -> bool {
+        let Some(pending_amount) = self.pending_amount else {
+            return false;
+        };
+        if now < self.price_change_effective_at || !self.is_open() {
+            return false;
+        }
+
+        let accepted = self.price_change_accepted || pending_amount <= self.amount_per_payment;
+        self.clear_price_change();
+        if accepted {
+            self.amount_per_payment = pending_amount;
+            return false;
+        }
+
+        self.status = SubscriptionStatus::Cancelled;
+        self.cancelled_at = now;
+        self.cancellation_reason = Some(CancellationReason::PriceChangeRejected);
+        true
+    }
+
+    pub fn clear_price_change(&mut self) {
+        self.pending_amount = None;
+        self.price_change_effective_at = 0;
+        self.price_change_accepted = false;
+    }
+
+    /// Whether a pause has run past the configured cap and billing should resume
+    pub fn pause_expired(&self, now: i64, max_pause_duration: i64) -> bool {
+        max_pause_duration > 0 && now.saturating_sub(self.paused_at) >= max_pause_duration
+    }
 }
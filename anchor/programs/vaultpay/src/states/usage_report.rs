@@ -0,0 +1,32 @@
+// states/usage_report.rs
+use anchor_lang::prelude::*;
+
+use crate::errors::VaultPayError;
+use crate::states::Subscription;
+
+/// Usage a vendor reported for one billing period of a metered subscription,
+/// kept on-chain as the audit trail for the charge
+#[account]
+pub struct UsageReport {
+    pub subscription: Pubkey,
+    pub period: u32, // payments made when the usage was reported
+    pub units: u64,
+    pub unit_price: u64,
+    pub reported_at: i64,
+    pub bump: u8,
+}
+
+impl UsageReport {
+    pub const LEN: usize = 32 + 4 + 8 + 8 + 8 + 1;
+
+    /// Units to bill for the subscription's current period. A report left
+    /// behind by an earlier subscription at the same address is refused.
+    pub fn units_for(&self, subscription: &Subscription) -> Result<u64> {
+        require!(
+            self.period == subscription.payments_made
+                && self.reported_at >= subscription.start_time,
+            VaultPayError::UsageNotReported
+        );
+        Ok(self.units)
+    }
+}
@@ -0,0 +1,42 @@
+// states/user_vault.rs
+use anchor_lang::prelude::*;
+
+use crate::errors::VaultPayError;
+
+/// Per-user settings that apply across every subscription paid from the vault
+#[account]
+pub struct UserVault {
+    pub user: Pubkey,
+    pub spending_limit: Option<u64>, // None leaves the vault uncapped
+    pub spending_window: i64,
+    pub window_started_at: i64,
+    pub spent_in_window: u64,
+    pub bump: u8,
+}
+
+impl UserVault {
+    pub const LEN: usize = 32 + (1 + 8) + 8 + 8 + 8 + 1;
+
+    /// Books a charge against the vault-wide limit, starting a new window
+    /// once the current one has run out
+    pub fn record_spend(&mut self, amount: u64, now: i64) -> Result<()> {
+        let Some(spending_limit) = self.spending_limit else {
+            return Ok(());
+        };
+
+        if now >= self.window_started_at.saturating_add(self.spending_window) {
+            self.window_started_at = now;
+            self.spent_in_window = 0;
+        }
+
+        let spent_in_window = self.spent_in_window
+            .checked_add(amount)
+            .ok_or(VaultPayError::MathOverflow)?;
+        require!(
+            spent_in_window <= spending_limit,
+            VaultPayError::VaultSpendingCapExceeded
+        );
+        self.spent_in_window = spent_in_window;
+        Ok(())
+    }
+}
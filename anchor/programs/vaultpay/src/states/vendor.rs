@@ -1,15 +1,63 @@
 // states/vendor.rs
 use anchor_lang::prelude::*;
 
+use crate::errors::VaultPayError;
+
+/// Most payees a vendor can split its revenue across
+pub const MAX_REVENUE_SPLITS: usize = 5;
+
 #[account]
 pub struct Vendor {
     pub authority: Pubkey,
     pub vendor_wallet: Pubkey,
+    pub payout_wallet: Pubkey,
     pub seed: u64,
-    pub is_whitelisted: bool,
+    pub status: VendorStatus, // 1 byte
+    pub revenue_splits: Vec<RevenueSplit>, // empty pays everything to vendor_wallet
     pub bump: u8,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct RevenueSplit {
+    pub token_account: Pubkey,
+    pub bps: u16,
+}
+
+impl RevenueSplit {
+    pub const LEN: usize = 32 + 2;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum VendorStatus {
+    Pending,
+    Approved,
+    Suspended,
+    Revoked,
+}
+
 impl Vendor {
-    pub const LEN: usize = 32 + 32 + 8 + 1 + 1;
+    pub const LEN: usize = 32 + 32 + 32 + 8 + 1 + (4 + MAX_REVENUE_SPLITS * RevenueSplit::LEN) + 1;
+
+    /// A split table has to hand out the whole vendor share, 10,000 bps
+    pub fn validate_splits(revenue_splits: &[RevenueSplit]) -> Result<()> {
+        if revenue_splits.is_empty() {
+            return Ok(());
+        }
+        require!(
+            revenue_splits.len() <= MAX_REVENUE_SPLITS,
+            VaultPayError::InvalidRevenueSplits
+        );
+
+        let mut total_bps: u32 = 0;
+        for split in revenue_splits {
+            require!(split.bps > 0, VaultPayError::InvalidRevenueSplits);
+            total_bps += split.bps as u32;
+        }
+        require!(total_bps == 10_000, VaultPayError::InvalidRevenueSplits);
+        Ok(())
+    }
+
+    pub fn is_in_good_standing(&self) -> bool {
+        self.status == VendorStatus::Approved
+    }
 }
@@ -13,6 +13,7 @@ import {
   createMint,
   getOrCreateAssociatedTokenAccount,
   getAssociatedTokenAddress,
+  getAssociatedTokenAddressSync,
   mintTo,
   TOKEN_PROGRAM_ID,
   getAccount,
@@ -51,6 +52,7 @@ describe("vaultpay", () => {
 
   let vaultpayAuthorityPDA: PublicKey;
   let vaultpayAuthorityBump: number;
+  let userVaultPDA: PublicKey;
 
   let yieldAccountPDA: PublicKey;
   let yieldAccountBump: number;
@@ -59,14 +61,259 @@ describe("vaultpay", () => {
   let vendorPDA: PublicKey;
   let vendorBump: number;
 
+  let planPDA: PublicKey;
+
   let subscriptionPDA: PublicKey;
   let subscriptionBump: number;
 
   const seed = new BN(12345); // Arbitrary seed value
+  const planSeed = new BN(1);
+  const subscriptionSeed = new BN(67890);
   const platformFee = 500; // 5% fee (500 basis points)
   const minSubscriptionDuration = 30 * 24 * 60 * 60; // 30 days
   const maxSubscriptionDuration = 365 * 24 * 60 * 60; // 1 year
 
+  const sleep = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms));
+
+  const clusterTime = async (): Promise<number> => {
+    const slot = await provider.connection.getSlot();
+    return await provider.connection.getBlockTime(slot);
+  };
+
+  const balanceOf = async (tokenAccount: PublicKey): Promise<number> =>
+    Number((await getAccount(provider.connection, tokenAccount)).amount);
+
+  const expectError = async (request: Promise<unknown>, code: string) => {
+    try {
+      await request;
+    } catch (err) {
+      assert.equal(err.error?.errorCode?.code, code);
+      return;
+    }
+    assert.fail(`Expected ${code}`);
+  };
+
+  // Vault accounts of a subscriber, see "Initialize user vault"
+  type Subscriber = {
+    keypair: Keypair;
+    tokenAccount: PublicKey;
+    vaultpayAuthority: PublicKey;
+    vaultpayAuthorityAta: PublicKey;
+    userVault: PublicKey;
+    yieldAccount: PublicKey;
+    yieldTokenAccount: PublicKey;
+  };
+
+  const subscriberFor = (keypair: Keypair): Subscriber => {
+    const [vaultpayAuthority] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vaultpay_authority"), configPDA.toBuffer(), keypair.publicKey.toBuffer()],
+      vaultpayProgram.programId
+    );
+    const [userVault] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user_vault"), configPDA.toBuffer(), keypair.publicKey.toBuffer()],
+      vaultpayProgram.programId
+    );
+    const [yieldAccount] = PublicKey.findProgramAddressSync(
+      [Buffer.from("yield_account"), yieldReservePDA.toBuffer(), vaultpayAuthority.toBuffer()],
+      mockYieldProgram.programId
+    );
+    return {
+      keypair,
+      tokenAccount: getAssociatedTokenAddressSync(tokenMint, keypair.publicKey),
+      vaultpayAuthority,
+      vaultpayAuthorityAta: getAssociatedTokenAddressSync(tokenMint, vaultpayAuthority, true),
+      userVault,
+      yieldAccount,
+      yieldTokenAccount: getAssociatedTokenAddressSync(tokenMint, yieldAccount, true),
+    };
+  };
+
+  // Funds a new user and deposits `deposit` tokens into a fresh vault
+  const setupSubscriber = async (deposit: number): Promise<Subscriber> => {
+    const keypair = Keypair.generate();
+    const signature = await provider.connection.requestAirdrop(
+      keypair.publicKey,
+      2 * anchor.web3.LAMPORTS_PER_SOL
+    );
+    await provider.connection.confirmTransaction(signature, "confirmed");
+
+    const subscriber = subscriberFor(keypair);
+    await getOrCreateAssociatedTokenAccount(provider.connection, keypair, tokenMint, keypair.publicKey);
+    await mintTo(provider.connection, keypair, tokenMint, subscriber.tokenAccount, authority, deposit);
+
+    await vaultpayProgram.methods
+      .initUser()
+      .accountsPartial({
+        user: keypair.publicKey,
+        tokenMint,
+        config: configPDA,
+        vaultpayAuthority: subscriber.vaultpayAuthority,
+        userVault: subscriber.userVault,
+        yieldReserve: yieldReservePDA,
+        yieldAccount: subscriber.yieldAccount,
+        yieldTokenAccount: subscriber.yieldTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([keypair])
+      .rpc();
+
+    await vaultpayProgram.methods
+      .deposit(new BN(deposit))
+      .accountsPartial({
+        user: keypair.publicKey,
+        config: configPDA,
+        tokenMint,
+        yieldReserve: yieldReservePDA,
+        vaultpayAuthority: subscriber.vaultpayAuthority,
+        yieldAccount: subscriber.yieldAccount,
+        yieldTokenAccount: subscriber.yieldTokenAccount,
+        userTokenAccount: subscriber.tokenAccount,
+        reserveTokenAccount: reserveTokenAccount,
+        yieldProgram: mockYieldProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([keypair])
+      .rpc();
+
+    return subscriber;
+  };
+
+  const subscriptionAddress = (owner: PublicKey, subscriptionSeed: BN): PublicKey =>
+    PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("subscription"),
+        vendorPDA.toBuffer(),
+        owner.toBuffer(),
+        subscriptionSeed.toArrayLike(Buffer, "le", 8),
+      ],
+      vaultpayProgram.programId
+    )[0];
+
+  // Accounts processPayment and crankPayment need to charge a subscription
+  const chargeAccounts = (subscriber: Subscriber, subscription: PublicKey) => ({
+    tokenMint,
+    config: configPDA,
+    subscription,
+    vendor: vendorPDA,
+    vaultpayAuthority: subscriber.vaultpayAuthority,
+    userVault: subscriber.userVault,
+    yieldReserve: yieldReservePDA,
+    yieldAccount: subscriber.yieldAccount,
+    yieldTokenAccount: subscriber.yieldTokenAccount,
+    reserveTokenAccount: reserveTokenAccount,
+    vaultpayAuthorityAta: subscriber.vaultpayAuthorityAta,
+    vendorTokenAccount: vendorPdaAta,
+    treasuryTokenAccount: treasuryTokenAccount,
+    usageReport: null,
+    affiliate: null,
+    affiliateEscrow: null,
+    tokenProgram: TOKEN_PROGRAM_ID,
+    systemProgram: SystemProgram.programId,
+  });
+
+  const processPayment = (subscriber: Subscriber, subscription: PublicKey, overrides = {}) =>
+    vaultpayProgram.methods
+      .processPayment()
+      .accountsPartial({
+        vendorSigner: vendorAuthority.publicKey,
+        ...chargeAccounts(subscriber, subscription),
+        ...overrides,
+      })
+      .signers([vendorAuthority])
+      .rpc();
+
+  // Subscribes to a plan, starting as soon as the cluster allows
+  const subscribe = async (
+    subscriber: Subscriber,
+    subscriptionSeed: BN,
+    plan: PublicKey
+  ): Promise<PublicKey> => {
+    const subscription = subscriptionAddress(subscriber.keypair.publicKey, subscriptionSeed);
+    const startTime = new BN((await clusterTime()) + 2);
+
+    await vaultpayProgram.methods
+      .initSubscription(subscriptionSeed, startTime, null)
+      .accountsPartial({
+        user: subscriber.keypair.publicKey,
+        tokenMint,
+        config: configPDA,
+        vendor: vendorPDA,
+        plan,
+        subscription,
+        affiliate: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([subscriber.keypair])
+      .rpc();
+
+    // Wait for the first payment to become due
+    await sleep(3000);
+    return subscription;
+  };
+
+  const createPlan = async (
+    seed: number,
+    amountPerPayment: number,
+    intervalSeconds: number,
+    numberOfPayments: number | null,
+    trialPeriod = 0,
+    billingMode: object = { flat: {} }
+  ): Promise<PublicKey> => {
+    const planSeedBn = new BN(seed);
+    const [plan] = PublicKey.findProgramAddressSync(
+      [Buffer.from("plan"), vendorPDA.toBuffer(), planSeedBn.toArrayLike(Buffer, "le", 8)],
+      vaultpayProgram.programId
+    );
+
+    await vaultpayProgram.methods
+      .createPlan(
+        planSeedBn,
+        new BN(amountPerPayment),
+        new BN(intervalSeconds),
+        numberOfPayments,
+        new BN(trialPeriod),
+        billingMode as any
+      )
+      .accountsPartial({
+        vendorSigner: vendorAuthority.publicKey,
+        tokenMint,
+        config: configPDA,
+        vendor: vendorPDA,
+        plan,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([vendorAuthority])
+      .rpc();
+    return plan;
+  };
+
+  const updateConfig = (params: object) =>
+    vaultpayProgram.methods
+      .updateConfig({
+        platformFee: null,
+        keeperFee: null,
+        referralFee: null,
+        referralPayments: null,
+        minSubscriptionDuration: null,
+        maxSubscriptionDuration: null,
+        maxPauseDuration: null,
+        cancellationRetention: null,
+        gracePeriod: null,
+        maxRetries: null,
+        retryInterval: null,
+        minPriceNotice: null,
+        ...params,
+      })
+      .accountsPartial({
+        authority: authority.publicKey,
+        tokenMint,
+        config: configPDA,
+        newTreasury: null,
+      })
+      .signers([authority])
+      .rpc();
 
   it("Airdrop SOL to authority, user, and vendor", async () => {
     await Promise.all(
@@ -188,7 +435,7 @@ describe("vaultpay", () => {
   it("Initialize the vaultpay program", async () => {
     // Derive the config PDA
     [configPDA, configBump] = await PublicKey.findProgramAddress(
-      [Buffer.from("config"), tokenMint.toBuffer(), authority.publicKey.toBuffer()],
+      [Buffer.from("config"), tokenMint.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
       vaultpayProgram.programId
     );
 
@@ -223,6 +470,37 @@ describe("vaultpay", () => {
     console.log("Vaultpay initialized:", tx);
   });
 
+  it("Rejects a platform fee above the cap", async () => {
+    try {
+      await vaultpayProgram.methods
+        .updateConfig({
+          platformFee: 5_000,
+          keeperFee: null,
+          referralFee: null,
+          referralPayments: null,
+          minSubscriptionDuration: null,
+          maxSubscriptionDuration: null,
+          maxPauseDuration: null,
+          cancellationRetention: null,
+          gracePeriod: null,
+          maxRetries: null,
+          retryInterval: null,
+          minPriceNotice: null,
+        })
+        .accountsPartial({
+          authority: authority.publicKey,
+          tokenMint,
+          config: configPDA,
+          newTreasury: null,
+        })
+        .signers([authority])
+        .rpc();
+      assert.fail("Platform fee above the cap should fail");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "PlatformFeeTooHigh");
+    }
+  });
+
   it("Initialize user vault", async () => {
     // Derive the vaultpay authority PDA
     [vaultpayAuthorityPDA, vaultpayAuthorityBump] =
@@ -231,6 +509,11 @@ describe("vaultpay", () => {
         vaultpayProgram.programId
       );
 
+    [userVaultPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user_vault"), configPDA.toBuffer(), user.publicKey.toBuffer()],
+      vaultpayProgram.programId
+    );
+
     // Derive yield account PDA
     [yieldAccountPDA, yieldAccountBump] = await PublicKey.findProgramAddress(
       [Buffer.from("yield_account"), yieldReservePDA.toBuffer(), vaultpayAuthorityPDA.toBuffer()],
@@ -414,38 +697,135 @@ describe("vaultpay", () => {
     //   console.log("No logs found for the transaction");
     // }
     console.log("Vendor initialized:", tx);
+
+    const vendorAccount = await vaultpayProgram.account.vendor.fetch(vendorPDA);
+    assert.ok(vendorAccount.status.pending, "New vendors should be pending");
+  });
+
+  it("Approve vendor", async () => {
+    const tx = await vaultpayProgram.methods
+      .approveVendor()
+      .accountsPartial({
+        authority: authority.publicKey,
+        tokenMint,
+        config: configPDA,
+        vendor: vendorPDA,
+      })
+      .signers([authority])
+      .rpc();
+
+    console.log("Vendor approved:", tx);
+
+    const vendorAccount = await vaultpayProgram.account.vendor.fetch(vendorPDA);
+    assert.ok(vendorAccount.status.approved, "Vendor should be approved");
+  });
+
+  it("Create plan", async () => {
+    [planPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("plan"), vendorPDA.toBuffer(), planSeed.toArrayLike(Buffer, "le", 8)],
+      vaultpayProgram.programId
+    );
+
+    const amountPerPayment = new BN(100_000_000); // 100 tokens per payment
+    const intervalSeconds = new BN(30 * 24 * 60 * 60); // Monthly
+    const numberOfPayments = 3; // 3 payments
+    const trialPeriod = new BN(0); // No trial
+
+    const tx = await vaultpayProgram.methods
+      .createPlan(planSeed, amountPerPayment, intervalSeconds, numberOfPayments, trialPeriod, { flat: {} })
+      .accountsPartial({
+        vendorSigner: vendorAuthority.publicKey,
+        tokenMint,
+        config: configPDA,
+        vendor: vendorPDA,
+        plan: planPDA,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([vendorAuthority])
+      .rpc();
+
+    console.log("Plan created:", tx);
+
+    const planAccount = await vaultpayProgram.account.plan.fetch(planPDA);
+    assert.ok(planAccount.active, "New plans should be active");
+  });
+
+  it("Rejects a plan shorter than the configured minimum", async () => {
+    const shortPlanSeed = new BN(2);
+    const [shortPlanPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("plan"), vendorPDA.toBuffer(), shortPlanSeed.toArrayLike(Buffer, "le", 8)],
+      vaultpayProgram.programId
+    );
+
+    try {
+      await vaultpayProgram.methods
+        .createPlan(
+          shortPlanSeed,
+          new BN(100_000_000),
+          new BN(24 * 60 * 60),
+          1, // A single daily payment is below the 30 day minimum
+          new BN(0),
+          { flat: {} }
+        )
+        .accountsPartial({
+          vendorSigner: vendorAuthority.publicKey,
+          tokenMint,
+          config: configPDA,
+          vendor: vendorPDA,
+          plan: shortPlanPDA,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([vendorAuthority])
+        .rpc();
+      assert.fail("Plan below the minimum duration should fail");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "SubscriptionTooShort");
+    }
   });
 
   it("Initialize subscription", async () => {
     // Derive subscription PDA
     [subscriptionPDA, subscriptionBump] = await PublicKey.findProgramAddressSync(
-      [Buffer.from("subscription"), vendorPDA.toBuffer(), user.publicKey.toBuffer()],
+      [
+        Buffer.from("subscription"),
+        vendorPDA.toBuffer(),
+        user.publicKey.toBuffer(),
+        subscriptionSeed.toArrayLike(Buffer, "le", 8),
+      ],
       vaultpayProgram.programId
     );
 
-    const amountPerPayment = new BN(100_000_000); // 100 tokens per payment
-    const numberOfPayments = 3; // 3 payments
-    const startTime = new BN(Math.floor(Date.now() / 1000)); // Now
+    // Start slightly ahead of the cluster clock, start times in the past are rejected
+    const slot = await provider.connection.getSlot();
+    const clusterTime = await provider.connection.getBlockTime(slot);
+    const startTime = new BN(clusterTime + 2);
 
     const tx = await vaultpayProgram.methods
-      .initSubscription(
-        new BN(67890), // seed
-        amountPerPayment,
-        numberOfPayments,
-        startTime
-      )
+      .initSubscription(subscriptionSeed, startTime, null)
       .accountsPartial({
         user: user.publicKey,
         tokenMint,
         config: configPDA,
         vendor: vendorPDA,
+        plan: planPDA,
         subscription: subscriptionPDA,
+        affiliate: null,
         systemProgram: SystemProgram.programId,
       })
       .signers([user])
       .rpc();
 
     console.log("Subscription initialized:", tx);
+
+    const subscriptionAccount = await vaultpayProgram.account.subscription.fetch(subscriptionPDA);
+    assert.equal(
+      subscriptionAccount.amountPerPayment.toString(),
+      "100000000",
+      "Subscription should copy the plan price"
+    );
+
+    // Wait for the first payment to become due
+    await new Promise(resolve => setTimeout(resolve, 3000));
   });
 
   it("Process payment", async () => {
@@ -460,11 +840,15 @@ describe("vaultpay", () => {
         // vendorAuthority: vendorAuthority.publicKey,
         // user: user.publicKey,
         vaultpayAuthority: vaultpayAuthorityPDA,
+        userVault: userVaultPDA,
         yieldReserve: yieldReservePDA,
         yieldAccount: yieldAccountPDA,
         yieldTokenAccount: yieldTokenAccount,
-        vendorTokenAccount: vendorTokenAccount,
+        vendorTokenAccount: vendorPdaAta,
         treasuryTokenAccount: treasuryTokenAccount,
+        usageReport: null,
+        affiliate: null,
+        affiliateEscrow: null,
         tokenProgram: TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
       }).instruction();
@@ -493,6 +877,137 @@ describe("vaultpay", () => {
     // Fetch subscription account and assert payments made increased
     const subscriptionAccount = await vaultpayProgram.account.subscription.fetch(subscriptionPDA);
     assert.equal(subscriptionAccount.paymentsMade, 1, "Payments made should be 1");
+    assert.equal(
+      subscriptionAccount.nextPaymentDue.toString(),
+      subscriptionAccount.startTime.add(subscriptionAccount.intervalSeconds).toString(),
+      "Next payment should be due one interval after start"
+    );
+  });
+
+  it("Vendor withdraws to payout wallet", async () => {
+    const vendorPdaAtaBefore = await getAccount(provider.connection, vendorPdaAta);
+    const payoutBefore = await getAccount(provider.connection, vendorTokenAccount);
+
+    const tx = await vaultpayProgram.methods
+      .vendorWithdraw(new BN(vendorPdaAtaBefore.amount.toString()))
+      .accountsPartial({
+        vendorSigner: vendorAuthority.publicKey,
+        tokenMint,
+        config: configPDA,
+        vendor: vendorPDA,
+        vendorTokenAccount: vendorPdaAta,
+        payoutWallet: vendorTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([vendorAuthority])
+      .rpc();
+
+    console.log("Vendor withdrew:", tx);
+
+    const payoutAfter = await getAccount(provider.connection, vendorTokenAccount);
+    assert.equal(
+      (payoutAfter.amount - payoutBefore.amount).toString(),
+      vendorPdaAtaBefore.amount.toString(),
+      "Payout wallet should receive the vendor balance"
+    );
+  });
+
+  it("Rejects a payment before it is due", async () => {
+    try {
+      await vaultpayProgram.methods
+        .processPayment()
+        .accountsPartial({
+          vendorSigner: vendorAuthority.publicKey,
+          tokenMint,
+          config: configPDA,
+          subscription: subscriptionPDA,
+          vendor: vendorPDA,
+          vaultpayAuthority: vaultpayAuthorityPDA,
+          userVault: userVaultPDA,
+          yieldReserve: yieldReservePDA,
+          yieldAccount: yieldAccountPDA,
+          yieldTokenAccount: yieldTokenAccount,
+          vendorTokenAccount: vendorPdaAta,
+          treasuryTokenAccount: treasuryTokenAccount,
+          usageReport: null,
+          affiliate: null,
+          affiliateEscrow: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([vendorAuthority])
+        .rpc();
+      assert.fail("Payment before the due date should fail");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "PaymentNotDue");
+    }
+  });
+
+  it("Vendor proposes a price change and the user accepts it", async () => {
+    const slot = await provider.connection.getSlot();
+    const clusterTime = await provider.connection.getBlockTime(slot);
+    // At least a month of notice, the configured minimum and the billing interval
+    const effectiveAt = new BN(clusterTime + 31 * 24 * 60 * 60);
+
+    await vaultpayProgram.methods
+      .proposePriceChange(new BN(1_500_000), effectiveAt)
+      .accountsPartial({
+        vendorSigner: vendorAuthority.publicKey,
+        tokenMint,
+        config: configPDA,
+        vendor: vendorPDA,
+        subscription: subscriptionPDA,
+      })
+      .signers([vendorAuthority])
+      .rpc();
+
+    await vaultpayProgram.methods
+      .acceptPriceChange()
+      .accountsPartial({
+        user: user.publicKey,
+        subscription: subscriptionPDA,
+      })
+      .signers([user])
+      .rpc();
+
+    const subscriptionAccount = await vaultpayProgram.account.subscription.fetch(subscriptionPDA);
+    assert.ok(subscriptionAccount.priceChangeAccepted, "Price change should be accepted");
+    assert.ok(subscriptionAccount.pendingAmount.eq(new BN(1_500_000)));
+  });
+
+  it("Pause and resume subscription", async () => {
+    const accounts = {
+      user: user.publicKey,
+      tokenMint,
+      config: configPDA,
+      vendor: vendorPDA,
+      subscription: subscriptionPDA,
+    };
+
+    await vaultpayProgram.methods
+      .pauseSubscription()
+      .accountsPartial(accounts)
+      .signers([user])
+      .rpc();
+
+    let subscriptionAccount = await vaultpayProgram.account.subscription.fetch(subscriptionPDA);
+    assert.ok(subscriptionAccount.status.paused, "Subscription should be paused");
+    const dueBeforePause = subscriptionAccount.nextPaymentDue;
+
+    await new Promise(resolve => setTimeout(resolve, 2000));
+
+    await vaultpayProgram.methods
+      .resumeSubscription()
+      .accountsPartial(accounts)
+      .signers([user])
+      .rpc();
+
+    subscriptionAccount = await vaultpayProgram.account.subscription.fetch(subscriptionPDA);
+    assert.ok(subscriptionAccount.status.active, "Subscription should be active again");
+    assert.ok(
+      subscriptionAccount.nextPaymentDue.gt(dueBeforePause),
+      "Next payment should move by the paused time"
+    );
   });
 
   it("Cancel subscription", async () => {
@@ -516,8 +1031,108 @@ describe("vaultpay", () => {
     console.log("Subscription canceled:", txSignature);
 
     // Fetch subscription account and assert status is canceled
-    // const subscriptionAccount = await vaultpayProgram.account.subscription.fetch(subscriptionPDA);
-    // assert.equal(subscriptionAccount.status.toString(), "cancelled", "Subscription status should be cancelled");
+    const subscriptionAccount = await vaultpayProgram.account.subscription.fetch(subscriptionPDA);
+    assert.ok(subscriptionAccount.status.cancelled, "Subscription status should be cancelled");
+    assert.equal(subscriptionAccount.paymentsMade, 1, "Cancellation record should keep payments made");
+  });
+
+  it("Keeps a cancelled subscription until the retention period ends", async () => {
+    try {
+      await vaultpayProgram.methods
+        .closeSubscription()
+        .accountsPartial({
+          user: user.publicKey,
+          tokenMint,
+          config: configPDA,
+          vendor: vendorPDA,
+          subscription: subscriptionPDA,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
+      assert.fail("Closing an unacknowledged cancellation should fail");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "RetentionPeriodActive");
+    }
+  });
+
+  it("Close subscription after the vendor acknowledges the cancellation", async () => {
+    await vaultpayProgram.methods
+      .acknowledgeCancellation()
+      .accountsPartial({
+        vendorSigner: vendorAuthority.publicKey,
+        tokenMint,
+        config: configPDA,
+        vendor: vendorPDA,
+        subscription: subscriptionPDA,
+      })
+      .signers([vendorAuthority])
+      .rpc();
+
+    const tx = await vaultpayProgram.methods
+      .closeSubscription()
+      .accountsPartial({
+        user: user.publicKey,
+        tokenMint,
+        config: configPDA,
+        vendor: vendorPDA,
+        subscription: subscriptionPDA,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+
+    console.log("Subscription closed:", tx);
+
+    const subscriptionInfo = await provider.connection.getAccountInfo(subscriptionPDA);
+    assert.isNull(subscriptionInfo, "Subscription account should be closed");
+  });
+
+  it("Pause blocks deposits", async () => {
+    await vaultpayProgram.methods
+      .pause()
+      .accountsPartial({
+        authority: authority.publicKey,
+        tokenMint,
+        config: configPDA,
+      })
+      .signers([authority])
+      .rpc();
+
+    try {
+      await vaultpayProgram.methods
+        .deposit(new BN(1_000_000))
+        .accountsPartial({
+          user: user.publicKey,
+          config: configPDA,
+          tokenMint,
+          yieldReserve: yieldReservePDA,
+          vaultpayAuthority: vaultpayAuthorityPDA,
+          yieldAccount: yieldAccountPDA,
+          yieldTokenAccount: yieldTokenAccount,
+          userTokenAccount: userTokenAccount,
+          reserveTokenAccount: reserveTokenAccount,
+          yieldProgram: mockYieldProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
+      assert.fail("Deposit while paused should fail");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "ProgramPaused");
+    }
+
+    await vaultpayProgram.methods
+      .unpause()
+      .accountsPartial({
+        authority: authority.publicKey,
+        tokenMint,
+        config: configPDA,
+      })
+      .signers([authority])
+      .rpc();
   });
 
   it("Withdraw funds from vault", async () => {
@@ -589,4 +1204,962 @@ describe("vaultpay", () => {
     const authorityAtaBalanceAfter = await getAccount(provider.connection, authorityTokenAccount);
     console.log("Authority ATA balance after claiming treasury:", authorityAtaBalanceAfter.amount.toString());
   });
+
+  it("Vendor cancels with a reason and refunds the unused part of the period", async () => {
+    const subscriber = subscriberFor(user);
+    const subscription = await subscribe(subscriber, new BN(1_201), planPDA);
+    await processPayment(subscriber, subscription);
+
+    // The vendor only kept its share of the charge, top its wallet up for the refund
+    await mintTo(provider.connection, authority, tokenMint, vendorPdaAta, authority, 10_000_000);
+    const userBalanceBefore = await balanceOf(userTokenAccount);
+
+    await vaultpayProgram.methods
+      .vendorCancelSubscription({ serviceDiscontinued: {} }, true)
+      .accountsPartial({
+        vendorSigner: vendorAuthority.publicKey,
+        tokenMint,
+        config: configPDA,
+        vendor: vendorPDA,
+        subscription,
+        vendorTokenAccount: vendorPdaAta,
+        userTokenAccount,
+        refundSource: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([vendorAuthority])
+      .rpc();
+
+    const subscriptionAccount = await vaultpayProgram.account.subscription.fetch(subscription);
+    assert.ok(subscriptionAccount.status.cancelled, "Subscription should be cancelled");
+    assert.ok(
+      subscriptionAccount.cancellationReason.serviceDiscontinued,
+      "Cancellation should record the vendor's reason"
+    );
+    assert.ok(subscriptionAccount.vendorAcknowledged, "Vendor cancellations are acknowledged");
+
+    // Almost the whole period is left, so almost the whole charge comes back
+    const refund = (await balanceOf(userTokenAccount)) - userBalanceBefore;
+    const lastPayment = subscriptionAccount.lastPaymentAmount.toNumber();
+    assert.equal(lastPayment, 100_000_000);
+    assert.ok(refund <= lastPayment, "Refund cannot exceed the last charge");
+    assert.ok(refund >= lastPayment * 0.99, "Refund should cover the unused period");
+
+    await expectError(processPayment(subscriber, subscription), "SubscriptionNotActive");
+  });
+
+  it("Suspends a past due subscription only after spaced retries", async () => {
+    await updateConfig({ gracePeriod: new BN(0), retryInterval: new BN(2) });
+
+    // One token in the vault cannot cover a 100 token charge
+    const subscriber = await setupSubscriber(1_000_000);
+    const subscription = await subscribe(subscriber, new BN(1_301), planPDA);
+
+    await processPayment(subscriber, subscription);
+    let subscriptionAccount = await vaultpayProgram.account.subscription.fetch(subscription);
+    assert.ok(subscriptionAccount.status.pastDue, "First miss should leave the subscription past due");
+    assert.equal(subscriptionAccount.failedAttempts, 1);
+
+    // Retrying straight away does not count against the retries
+    await expectError(processPayment(subscriber, subscription), "RetryTooSoon");
+
+    await sleep(4000);
+    await processPayment(subscriber, subscription);
+    subscriptionAccount = await vaultpayProgram.account.subscription.fetch(subscription);
+    assert.ok(subscriptionAccount.status.pastDue, "Second miss should still be past due");
+    assert.equal(subscriptionAccount.failedAttempts, 2);
+
+    await sleep(4000);
+    await processPayment(subscriber, subscription);
+    subscriptionAccount = await vaultpayProgram.account.subscription.fetch(subscription);
+    assert.ok(subscriptionAccount.status.suspended, "Running out of retries should suspend");
+    assert.equal(subscriptionAccount.failedAttempts, 3);
+
+    await updateConfig({ gracePeriod: new BN(3 * 24 * 60 * 60), retryInterval: new BN(24 * 60 * 60) });
+  });
+
+  it("Refuses to charge or crank against another user's yield position", async () => {
+    const subscriber = await setupSubscriber(500_000_000);
+    const stranger = await setupSubscriber(1);
+    const subscription = await subscribe(subscriber, new BN(1_351), planPDA);
+
+    // The stranger's nearly empty position would otherwise read as a short vault
+    const strangerPosition = {
+      yieldAccount: stranger.yieldAccount,
+      yieldTokenAccount: stranger.yieldTokenAccount,
+    };
+    await expectError(processPayment(subscriber, subscription, strangerPosition), "InvalidYieldAccount");
+
+    // Nor can a keeper crank a funded subscription into past due with it
+    const keeper = Keypair.generate();
+    const keeperTokenAccount = (
+      await getOrCreateAssociatedTokenAccount(provider.connection, authority, tokenMint, keeper.publicKey)
+    ).address;
+    await expectError(
+      vaultpayProgram.methods
+        .crankPayment()
+        .accountsPartial({
+          keeper: keeper.publicKey,
+          keeperTokenAccount,
+          ...chargeAccounts(subscriber, subscription),
+          ...strangerPosition,
+        })
+        .signers([keeper])
+        .rpc(),
+      "InvalidYieldAccount"
+    );
+
+    const subscriptionAccount = await vaultpayProgram.account.subscription.fetch(subscription);
+    assert.ok(subscriptionAccount.status.active, "No miss is recorded");
+    assert.equal(subscriptionAccount.failedAttempts, 0);
+  });
+
+  it("Keeper cranks a due subscription and earns its tip", async () => {
+    await updateConfig({ keeperFee: 2_000 }); // 20% of the platform fee
+
+    const keeper = Keypair.generate();
+    const keeperTokenAccount = (
+      await getOrCreateAssociatedTokenAccount(provider.connection, authority, tokenMint, keeper.publicKey)
+    ).address;
+
+    const subscriber = await setupSubscriber(500_000_000);
+    const subscription = await subscribe(subscriber, new BN(1_401), planPDA);
+
+    const vendorBefore = await balanceOf(vendorPdaAta);
+    const treasuryBefore = await balanceOf(treasuryTokenAccount);
+
+    await vaultpayProgram.methods
+      .crankPayment()
+      .accountsPartial({
+        keeper: keeper.publicKey,
+        keeperTokenAccount,
+        ...chargeAccounts(subscriber, subscription),
+      })
+      .signers([keeper])
+      .rpc();
+
+    // 100 token charge: 5 token platform fee, 1 of which tips the keeper
+    assert.equal(await balanceOf(keeperTokenAccount), 1_000_000);
+    assert.equal((await balanceOf(treasuryTokenAccount)) - treasuryBefore, 4_000_000);
+    assert.equal((await balanceOf(vendorPdaAta)) - vendorBefore, 95_000_000);
+
+    const subscriptionAccount = await vaultpayProgram.account.subscription.fetch(subscription);
+    assert.equal(subscriptionAccount.paymentsMade, 1);
+
+    await updateConfig({ keeperFee: 0 });
+  });
+
+  it("Charges a mixed batch without failing on bad entries", async () => {
+    const subscriber = await setupSubscriber(500_000_000);
+    const other = await setupSubscriber(500_000_000);
+
+    const due = await subscribe(subscriber, new BN(1_501), planPDA);
+    const notDue = await subscribe(subscriber, new BN(1_502), planPDA);
+    const wrongVault = await subscribe(subscriber, new BN(1_503), planPDA);
+    await processPayment(subscriber, notDue);
+
+    // Accounts of one batch entry, see BATCH_ENTRY_ACCOUNTS
+    const entry = (vault: Subscriber, subscription: PublicKey) =>
+      [
+        subscription,
+        vault.vaultpayAuthority,
+        vault.yieldAccount,
+        vault.yieldTokenAccount,
+        vault.vaultpayAuthorityAta,
+        vault.userVault,
+      ].map((pubkey) => ({ pubkey, isWritable: true, isSigner: false }));
+
+    const signature = await vaultpayProgram.methods
+      .processPaymentsBatch(3)
+      .accountsPartial({
+        vendorSigner: vendorAuthority.publicKey,
+        tokenMint,
+        config: configPDA,
+        vendor: vendorPDA,
+        yieldReserve: yieldReservePDA,
+        vendorTokenAccount: vendorPdaAta,
+        reserveTokenAccount: reserveTokenAccount,
+        treasuryTokenAccount: treasuryTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts([
+        ...entry(subscriber, due),
+        ...entry(subscriber, notDue),
+        ...entry(other, wrongVault),
+      ])
+      .signers([vendorAuthority])
+      .rpc({ commitment: "confirmed" });
+
+    // The Vec<BatchPaymentResult> comes back as borsh encoded return data
+    const transaction = await provider.connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const returnLog = transaction.meta.logMessages.find((log) =>
+      log.startsWith(`Program return: ${vaultpayProgram.programId.toBase58()}`)
+    );
+    const returnData = Buffer.from(returnLog.split(" ").pop(), "base64");
+    assert.equal(returnData.readUInt32LE(0), 3);
+    // Charged, Skipped, Invalid
+    assert.deepEqual([...returnData.subarray(4)], [0, 1, 3]);
+
+    const dueAccount = await vaultpayProgram.account.subscription.fetch(due);
+    assert.equal(dueAccount.paymentsMade, 1);
+    const notDueAccount = await vaultpayProgram.account.subscription.fetch(notDue);
+    assert.equal(notDueAccount.paymentsMade, 1, "A subscription that is not due is left alone");
+    const wrongVaultAccount = await vaultpayProgram.account.subscription.fetch(wrongVault);
+    assert.equal(wrongVaultAccount.paymentsMade, 0, "Another user's vault is never charged");
+  });
+
+  it("Refuses charges during a trial and closes a subscription cancelled in it", async () => {
+    const trialPlan = await createPlan(20, 100_000_000, 30 * 24 * 60 * 60, 3, 7 * 24 * 60 * 60);
+    const subscriber = subscriberFor(user);
+    const subscription = await subscribe(subscriber, new BN(1_601), trialPlan);
+
+    const subscriptionAccount = await vaultpayProgram.account.subscription.fetch(subscription);
+    assert.isAbove(subscriptionAccount.trialEndsAt.toNumber(), await clusterTime());
+    await expectError(processPayment(subscriber, subscription), "TrialNotEnded");
+
+    await vaultpayProgram.methods
+      .cancelSubscription()
+      .accountsPartial({
+        user: user.publicKey,
+        subscription,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+
+    const closed = await vaultpayProgram.account.subscription.fetchNullable(subscription);
+    assert.isNull(closed, "Nothing was charged, so no record is kept");
+  });
+
+  // Affiliate accounts to pass along a charge, see "Credits referral shares to the affiliate escrow"
+  type Referral = { affiliate: PublicKey | null; affiliateEscrow: PublicKey | null };
+  const noReferral: Referral = { affiliate: null, affiliateEscrow: null };
+
+  const subscribeWithPayment = (subscriber: Subscriber, subscriptionSeed: BN, referral = noReferral) =>
+    vaultpayProgram.methods
+      .initSubscriptionWithPayment(subscriptionSeed)
+      .accountsPartial({
+        user: subscriber.keypair.publicKey,
+        tokenMint,
+        config: configPDA,
+        vendor: vendorPDA,
+        plan: planPDA,
+        subscription: subscriptionAddress(subscriber.keypair.publicKey, subscriptionSeed),
+        vaultpayAuthority: subscriber.vaultpayAuthority,
+        userVault: subscriber.userVault,
+        yieldAccount: subscriber.yieldAccount,
+        yieldReserve: yieldReservePDA,
+        yieldTokenAccount: subscriber.yieldTokenAccount,
+        vendorTokenAccount: vendorPdaAta,
+        reserveTokenAccount: reserveTokenAccount,
+        vaultpayAuthorityAta: subscriber.vaultpayAuthorityAta,
+        treasuryTokenAccount: treasuryTokenAccount,
+        ...referral,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([subscriber.keypair])
+      .rpc();
+
+  it("Creates a paid subscription only when the first charge clears", async () => {
+    // 50 tokens cannot cover the first 100 token period
+    const short = await setupSubscriber(50_000_000);
+    await expectError(subscribeWithPayment(short, new BN(1_701)), "InsufficientFunds");
+    const missing = await vaultpayProgram.account.subscription.fetchNullable(
+      subscriptionAddress(short.keypair.publicKey, new BN(1_701))
+    );
+    assert.isNull(missing, "No subscription is left behind when the charge fails");
+
+    const funded = await setupSubscriber(500_000_000);
+    const vendorBefore = await balanceOf(vendorPdaAta);
+    await subscribeWithPayment(funded, new BN(1_702));
+
+    const subscriptionAccount = await vaultpayProgram.account.subscription.fetch(
+      subscriptionAddress(funded.keypair.publicKey, new BN(1_702))
+    );
+    assert.equal(subscriptionAccount.paymentsMade, 1);
+    assert.equal(subscriptionAccount.lastPaymentAmount.toNumber(), 100_000_000);
+    assert.equal((await balanceOf(vendorPdaAta)) - vendorBefore, 95_000_000);
+  });
+
+  const changePlan = (subscriber: Subscriber, subscription: PublicKey, newPlan: PublicKey, referral = noReferral) =>
+    vaultpayProgram.methods
+      .changePlan()
+      .accountsPartial({
+        user: subscriber.keypair.publicKey,
+        tokenMint,
+        config: configPDA,
+        vendor: vendorPDA,
+        newPlan,
+        subscription,
+        vaultpayAuthority: subscriber.vaultpayAuthority,
+        userVault: subscriber.userVault,
+        yieldAccount: subscriber.yieldAccount,
+        yieldReserve: yieldReservePDA,
+        yieldTokenAccount: subscriber.yieldTokenAccount,
+        vendorTokenAccount: vendorPdaAta,
+        reserveTokenAccount: reserveTokenAccount,
+        vaultpayAuthorityAta: subscriber.vaultpayAuthorityAta,
+        treasuryTokenAccount: treasuryTokenAccount,
+        ...referral,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([subscriber.keypair])
+      .rpc();
+
+  it("Nets downgrade credit against a later upgrade", async () => {
+    const month = 30 * 24 * 60 * 60;
+    const cheaperPlan = await createPlan(21, 50_000_000, month, 3);
+    const pricierPlan = await createPlan(22, 200_000_000, month, 3);
+
+    const subscriber = await setupSubscriber(500_000_000);
+    const subscription = await subscribe(subscriber, new BN(1_801), planPDA);
+    await processPayment(subscriber, subscription);
+
+    // Downgrading right after paying credits about half of the 100 token period
+    await changePlan(subscriber, subscription, cheaperPlan);
+    let subscriptionAccount = await vaultpayProgram.account.subscription.fetch(subscription);
+    const credit = subscriptionAccount.creditBalance.toNumber();
+    assert.isAtLeast(credit, 49_900_000);
+    assert.isAtMost(credit, 50_000_000);
+
+    // Upgrading pays the 150 token difference for the period less that credit
+    const vendorBefore = await balanceOf(vendorPdaAta);
+    const treasuryBefore = await balanceOf(treasuryTokenAccount);
+    await changePlan(subscriber, subscription, pricierPlan);
+    const charged =
+      (await balanceOf(vendorPdaAta)) - vendorBefore + (await balanceOf(treasuryTokenAccount)) - treasuryBefore;
+    assert.isAtLeast(charged, 99_800_000);
+    assert.isAtMost(charged, 100_000_000);
+
+    subscriptionAccount = await vaultpayProgram.account.subscription.fetch(subscription);
+    assert.equal(subscriptionAccount.creditBalance.toNumber(), 0, "Credit is used up by the upgrade");
+    assert.equal(subscriptionAccount.amountPerPayment.toNumber(), 200_000_000);
+  });
+
+  it("Charges the old price until an unaccepted price change ends the subscription", async () => {
+    // Short open-ended plan so the notice period can run out during the test
+    await updateConfig({ minPriceNotice: new BN(0) });
+    const shortPlan = await createPlan(23, 10_000_000, 8, null);
+
+    const subscriber = await setupSubscriber(500_000_000);
+    const subscription = await subscribe(subscriber, new BN(1_901), shortPlan);
+
+    const proposePriceChange = (effectiveAt: number) =>
+      vaultpayProgram.methods
+        .proposePriceChange(new BN(20_000_000), new BN(effectiveAt))
+        .accountsPartial({
+          vendorSigner: vendorAuthority.publicKey,
+          tokenMint,
+          config: configPDA,
+          vendor: vendorPDA,
+          subscription,
+        })
+        .signers([vendorAuthority])
+        .rpc();
+
+    // Less than one billing interval of notice is refused
+    await expectError(proposePriceChange((await clusterTime()) + 2), "InvalidEffectiveDate");
+    await proposePriceChange((await clusterTime()) + 10);
+
+    await processPayment(subscriber, subscription);
+    let subscriptionAccount = await vaultpayProgram.account.subscription.fetch(subscription);
+    assert.equal(subscriptionAccount.lastPaymentAmount.toNumber(), 10_000_000, "Old price applies before the effective date");
+
+    await sleep(12000);
+    await processPayment(subscriber, subscription);
+    subscriptionAccount = await vaultpayProgram.account.subscription.fetch(subscription);
+    assert.ok(subscriptionAccount.status.cancelled, "Unaccepted price change cancels the subscription");
+    assert.ok(subscriptionAccount.cancellationReason.priceChangeRejected);
+    assert.equal(subscriptionAccount.paymentsMade, 1, "Nothing is charged at the new price");
+
+    await updateConfig({ minPriceNotice: new BN(30 * 24 * 60 * 60) });
+  });
+
+  it("Applies a price cut at its effective date without the user's consent", async () => {
+    await updateConfig({ minPriceNotice: new BN(0) });
+    const shortPlan = await createPlan(29, 10_000_000, 8, null);
+
+    const subscriber = await setupSubscriber(500_000_000);
+    const subscription = await subscribe(subscriber, new BN(1_902), shortPlan);
+    await processPayment(subscriber, subscription);
+
+    await vaultpayProgram.methods
+      .proposePriceChange(new BN(5_000_000), new BN((await clusterTime()) + 10))
+      .accountsPartial({
+        vendorSigner: vendorAuthority.publicKey,
+        tokenMint,
+        config: configPDA,
+        vendor: vendorPDA,
+        subscription,
+      })
+      .signers([vendorAuthority])
+      .rpc();
+
+    await sleep(12000);
+    await processPayment(subscriber, subscription);
+    const subscriptionAccount = await vaultpayProgram.account.subscription.fetch(subscription);
+    assert.ok(subscriptionAccount.status.active, "A cut never cancels the subscription");
+    assert.equal(subscriptionAccount.lastPaymentAmount.toNumber(), 5_000_000, "The lower price is charged");
+    assert.isNull(subscriptionAccount.pendingAmount);
+
+    await updateConfig({ minPriceNotice: new BN(30 * 24 * 60 * 60) });
+  });
+
+  it("Enforces subscription and vault spending caps", async () => {
+    const cappedPlan = await createPlan(24, 10_000_000, 4, null);
+    const subscriber = await setupSubscriber(500_000_000);
+
+    // Subscription cap: 15 tokens in total covers one 10 token period
+    const capped = await subscribe(subscriber, new BN(2_001), cappedPlan);
+    await vaultpayProgram.methods
+      .setSpendingCaps(new BN(15_000_000), null)
+      .accountsPartial({ user: subscriber.keypair.publicKey, subscription: capped })
+      .signers([subscriber.keypair])
+      .rpc();
+    await processPayment(subscriber, capped);
+    await sleep(5000);
+    await expectError(processPayment(subscriber, capped), "SpendingCapExceeded");
+
+    // Vault cap: 15 tokens per 10 second window across every subscription
+    const windowed = await subscribe(subscriber, new BN(2_002), cappedPlan);
+    await vaultpayProgram.methods
+      .setVaultSpendingCap(new BN(15_000_000), new BN(10))
+      .accountsPartial({
+        user: subscriber.keypair.publicKey,
+        tokenMint,
+        config: configPDA,
+        userVault: subscriber.userVault,
+      })
+      .signers([subscriber.keypair])
+      .rpc();
+    await processPayment(subscriber, windowed);
+    await sleep(5000);
+    await expectError(processPayment(subscriber, windowed), "VaultSpendingCapExceeded");
+
+    // Once the window runs out the vault can be charged again
+    const windowStart = (await vaultpayProgram.account.userVault.fetch(subscriber.userVault)).windowStartedAt;
+    await sleep(5000);
+    await processPayment(subscriber, windowed);
+    const userVault = await vaultpayProgram.account.userVault.fetch(subscriber.userVault);
+    assert.isAbove(userVault.windowStartedAt.toNumber(), windowStart.toNumber(), "A new window started");
+    assert.equal(userVault.spentInWindow.toNumber(), 10_000_000);
+  });
+
+  // Usage report for the subscription's current period
+  const usageReportFor = async (subscription: PublicKey): Promise<PublicKey> => {
+    const { startTime, paymentsMade } = await vaultpayProgram.account.subscription.fetch(subscription);
+    const period = Buffer.alloc(4);
+    period.writeUInt32LE(paymentsMade);
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("usage"), subscription.toBuffer(), startTime.toArrayLike(Buffer, "le", 8), period],
+      vaultpayProgram.programId
+    )[0];
+  };
+
+  const reportUsage = async (subscription: PublicKey, units: number): Promise<PublicKey> => {
+    const usageReport = await usageReportFor(subscription);
+    await vaultpayProgram.methods
+      .reportUsage(new BN(units))
+      .accountsPartial({
+        vendorSigner: vendorAuthority.publicKey,
+        tokenMint,
+        config: configPDA,
+        vendor: vendorPDA,
+        subscription,
+        usageReport,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([vendorAuthority])
+      .rpc();
+    return usageReport;
+  };
+
+  it("Bills metered usage up to the usage ceiling", async () => {
+    // 1 token per unit, with a default ceiling of 50 tokens a period
+    const meteredPlan = await createPlan(25, 50_000_000, 4, null, 0, {
+      metered: { unitPrice: new BN(1_000_000) },
+    });
+    const subscriber = await setupSubscriber(500_000_000);
+    const subscription = await subscribe(subscriber, new BN(2_101), meteredPlan);
+
+    await expectError(processPayment(subscriber, subscription), "UsageNotReported");
+
+    let usageReport = await reportUsage(subscription, 20);
+    await processPayment(subscriber, subscription, { usageReport });
+    let subscriptionAccount = await vaultpayProgram.account.subscription.fetch(subscription);
+    assert.equal(subscriptionAccount.lastPaymentAmount.toNumber(), 20_000_000, "Reported usage is billed");
+
+    // The user lowers the ceiling below what the vendor reports next
+    await vaultpayProgram.methods
+      .setUsageCeiling(new BN(30_000_000))
+      .accountsPartial({ user: subscriber.keypair.publicKey, subscription })
+      .signers([subscriber.keypair])
+      .rpc();
+
+    await sleep(5000);
+    usageReport = await reportUsage(subscription, 80);
+    await processPayment(subscriber, subscription, { usageReport });
+    subscriptionAccount = await vaultpayProgram.account.subscription.fetch(subscription);
+    assert.equal(subscriptionAccount.lastPaymentAmount.toNumber(), 30_000_000, "Usage is capped at the ceiling");
+    assert.equal(subscriptionAccount.paymentsMade, 2);
+  });
+
+  it("Refuses usage reported for a closed subscription recreated at the same address", async () => {
+    const meteredTrialPlan = await createPlan(28, 50_000_000, 4, null, 7 * 24 * 60 * 60, {
+      metered: { unitPrice: new BN(1_000_000) },
+    });
+    const subscriber = await setupSubscriber(500_000_000);
+    const subscriptionSeed = new BN(2_102);
+    let subscription = await subscribe(subscriber, subscriptionSeed, meteredTrialPlan);
+    const staleReport = await reportUsage(subscription, 40);
+
+    // Cancelling in the trial closes the subscription straight away
+    await vaultpayProgram.methods
+      .cancelSubscription()
+      .accountsPartial({
+        user: subscriber.keypair.publicKey,
+        subscription,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([subscriber.keypair])
+      .rpc();
+
+    subscription = await subscribe(subscriber, subscriptionSeed, meteredTrialPlan);
+    await expectError(processPayment(subscriber, subscription, { usageReport: staleReport }), "ConstraintSeeds");
+
+    // The vendor reports the new subscription's usage at a fresh address
+    const usageReport = await reportUsage(subscription, 10);
+    assert.notEqual(usageReport.toBase58(), staleReport.toBase58());
+  });
+
+  const streamRecipient = Keypair.generate();
+
+  const createStream = async (subscriber: Subscriber, streamSeed: BN, ratePerSecond: number) => {
+    const [stream] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("stream"),
+        configPDA.toBuffer(),
+        subscriber.keypair.publicKey.toBuffer(),
+        streamSeed.toArrayLike(Buffer, "le", 8),
+      ],
+      vaultpayProgram.programId
+    );
+    await vaultpayProgram.methods
+      .createStream(streamSeed, streamRecipient.publicKey, new BN(ratePerSecond))
+      .accountsPartial({
+        user: subscriber.keypair.publicKey,
+        tokenMint,
+        config: configPDA,
+        stream,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([subscriber.keypair])
+      .rpc();
+    return stream;
+  };
+
+  const withdrawStreamed = (subscriber: Subscriber, stream: PublicKey) =>
+    vaultpayProgram.methods
+      .withdrawStreamed()
+      .accountsPartial({
+        recipient: streamRecipient.publicKey,
+        tokenMint,
+        config: configPDA,
+        stream,
+        vaultpayAuthority: subscriber.vaultpayAuthority,
+        userVault: subscriber.userVault,
+        yieldAccount: subscriber.yieldAccount,
+        yieldReserve: yieldReservePDA,
+        yieldTokenAccount: subscriber.yieldTokenAccount,
+        recipientTokenAccount: getAssociatedTokenAddressSync(tokenMint, streamRecipient.publicKey),
+        reserveTokenAccount: reserveTokenAccount,
+        vaultpayAuthorityAta: subscriber.vaultpayAuthorityAta,
+        treasuryTokenAccount: treasuryTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([streamRecipient])
+      .rpc();
+
+  // Recipient's share of a withdrawal once the 5% platform fee is taken
+  const afterFee = (amount: number) => amount - Math.floor((amount * platformFee) / 10_000);
+
+  it("Streams accrue per second and stop accruing once stopped", async () => {
+    const recipientTokenAccount = (
+      await getOrCreateAssociatedTokenAccount(provider.connection, authority, tokenMint, streamRecipient.publicKey)
+    ).address;
+    const subscriber = await setupSubscriber(500_000_000);
+    const stream = await createStream(subscriber, new BN(2_201), 1_000_000);
+
+    await sleep(3000);
+    let recipientBefore = await balanceOf(recipientTokenAccount);
+    await withdrawStreamed(subscriber, stream);
+    let streamAccount = await vaultpayProgram.account.stream.fetch(stream);
+    const firstWithdrawal = streamAccount.totalWithdrawn.toNumber();
+    assert.isAtLeast(firstWithdrawal, 2_000_000, "A few seconds worth of tokens accrued");
+    assert.equal((await balanceOf(recipientTokenAccount)) - recipientBefore, afterFee(firstWithdrawal));
+
+    await vaultpayProgram.methods
+      .stopStream()
+      .accountsPartial({
+        user: subscriber.keypair.publicKey,
+        tokenMint,
+        config: configPDA,
+        stream,
+      })
+      .signers([subscriber.keypair])
+      .rpc();
+
+    // Everything streamed up to the stop is still paid out
+    await sleep(2000);
+    recipientBefore = await balanceOf(recipientTokenAccount);
+    await withdrawStreamed(subscriber, stream);
+    streamAccount = await vaultpayProgram.account.stream.fetch(stream);
+    const streamed = (streamAccount.stoppedAt.toNumber() - streamAccount.startTime.toNumber()) * 1_000_000;
+    assert.equal(streamAccount.totalWithdrawn.toNumber(), streamed);
+    assert.equal(
+      (await balanceOf(recipientTokenAccount)) - recipientBefore,
+      afterFee(streamed - firstWithdrawal)
+    );
+
+    await sleep(2000);
+    await expectError(withdrawStreamed(subscriber, stream), "NothingToWithdraw");
+  });
+
+  it("Streams pay out what a short vault can cover and keep the rest owed", async () => {
+    const recipientTokenAccount = getAssociatedTokenAddressSync(tokenMint, streamRecipient.publicKey);
+    const subscriber = await setupSubscriber(3_000_000);
+    const stream = await createStream(subscriber, new BN(2_202), 1_000_000);
+
+    await sleep(5000);
+    const recipientBefore = await balanceOf(recipientTokenAccount);
+    await withdrawStreamed(subscriber, stream);
+
+    const streamAccount = await vaultpayProgram.account.stream.fetch(stream);
+    assert.equal(streamAccount.totalWithdrawn.toNumber(), 3_000_000, "Only the vault balance is withdrawn");
+    assert.equal((await balanceOf(recipientTokenAccount)) - recipientBefore, afterFee(3_000_000));
+
+    await expectError(withdrawStreamed(subscriber, stream), "InsufficientFunds");
+  });
+
+  const createInvoice = async (
+    subscriber: Subscriber,
+    invoiceSeed: BN,
+    amount: number,
+    dueAt: number,
+    expiresAt: number
+  ): Promise<PublicKey> => {
+    const [invoice] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("invoice"),
+        vendorPDA.toBuffer(),
+        subscriber.keypair.publicKey.toBuffer(),
+        invoiceSeed.toArrayLike(Buffer, "le", 8),
+      ],
+      vaultpayProgram.programId
+    );
+    await vaultpayProgram.methods
+      .createInvoice(
+        invoiceSeed,
+        subscriber.keypair.publicKey,
+        new BN(amount),
+        new BN(dueAt),
+        new BN(expiresAt),
+        "Setup fee"
+      )
+      .accountsPartial({
+        vendorSigner: vendorAuthority.publicKey,
+        tokenMint,
+        config: configPDA,
+        vendor: vendorPDA,
+        invoice,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([vendorAuthority])
+      .rpc();
+    return invoice;
+  };
+
+  const payInvoice = (subscriber: Subscriber, invoice: PublicKey) =>
+    vaultpayProgram.methods
+      .payInvoice()
+      .accountsPartial({
+        user: subscriber.keypair.publicKey,
+        tokenMint,
+        config: configPDA,
+        vendor: vendorPDA,
+        invoice,
+        vaultpayAuthority: subscriber.vaultpayAuthority,
+        userVault: subscriber.userVault,
+        yieldAccount: subscriber.yieldAccount,
+        yieldReserve: yieldReservePDA,
+        yieldTokenAccount: subscriber.yieldTokenAccount,
+        vendorTokenAccount: vendorPdaAta,
+        reserveTokenAccount: reserveTokenAccount,
+        vaultpayAuthorityAta: subscriber.vaultpayAuthorityAta,
+        treasuryTokenAccount: treasuryTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([subscriber.keypair])
+      .rpc();
+
+  const vendorInvoiceAccounts = (invoice: PublicKey) => ({
+    vendorSigner: vendorAuthority.publicKey,
+    tokenMint,
+    config: configPDA,
+    vendor: vendorPDA,
+    invoice,
+  });
+
+  const closeInvoice = (invoice: PublicKey) =>
+    vaultpayProgram.methods
+      .closeInvoice()
+      .accountsPartial({ ...vendorInvoiceAccounts(invoice), systemProgram: SystemProgram.programId })
+      .signers([vendorAuthority])
+      .rpc();
+
+  it("Pays an invoice from its due date and closes it once paid", async () => {
+    const subscriber = await setupSubscriber(500_000_000);
+    const now = await clusterTime();
+    const invoice = await createInvoice(subscriber, new BN(2_301), 25_000_000, now + 3, now + 3600);
+
+    await expectError(payInvoice(subscriber, invoice), "InvoiceNotDue");
+
+    await sleep(4000);
+    const vendorBefore = await balanceOf(vendorPdaAta);
+    await payInvoice(subscriber, invoice);
+    const invoiceAccount = await vaultpayProgram.account.invoice.fetch(invoice);
+    assert.ok(invoiceAccount.status.paid, "Invoice should be paid");
+    assert.isAbove(invoiceAccount.paidAt.toNumber(), 0);
+    assert.equal((await balanceOf(vendorPdaAta)) - vendorBefore, 23_750_000, "Vendor gets the amount less the platform fee");
+
+    await expectError(payInvoice(subscriber, invoice), "InvoiceNotOpen");
+
+    await closeInvoice(invoice);
+    assert.isNull(await vaultpayProgram.account.invoice.fetchNullable(invoice));
+  });
+
+  it("Voids and expires open invoices", async () => {
+    const subscriber = await setupSubscriber(500_000_000);
+    const now = await clusterTime();
+
+    const voided = await createInvoice(subscriber, new BN(2_302), 25_000_000, now, now + 3600);
+    await vaultpayProgram.methods
+      .voidInvoice()
+      .accountsPartial(vendorInvoiceAccounts(voided))
+      .signers([vendorAuthority])
+      .rpc();
+    assert.ok((await vaultpayProgram.account.invoice.fetch(voided)).status.void);
+    await expectError(payInvoice(subscriber, voided), "InvoiceNotOpen");
+    await closeInvoice(voided);
+
+    const lapsing = await createInvoice(subscriber, new BN(2_303), 25_000_000, now, now + 3);
+    const expireInvoice = () =>
+      vaultpayProgram.methods.expireInvoice().accountsPartial({ invoice: lapsing }).rpc();
+
+    await expectError(expireInvoice(), "InvoiceNotExpired");
+    await expectError(closeInvoice(lapsing), "InvoiceStillOpen");
+
+    await sleep(4000);
+    await expectError(payInvoice(subscriber, lapsing), "InvoiceExpired");
+    await expireInvoice();
+    assert.ok((await vaultpayProgram.account.invoice.fetch(lapsing)).status.expired);
+
+    await closeInvoice(lapsing);
+    assert.isNull(await vaultpayProgram.account.invoice.fetchNullable(lapsing));
+  });
+
+  it("Splits vendor revenue across payees, the last taking the rounding remainder", async () => {
+    const payees = [];
+    for (let i = 0; i < 3; i++) {
+      const payee = Keypair.generate();
+      payees.push(
+        (await getOrCreateAssociatedTokenAccount(provider.connection, authority, tokenMint, payee.publicKey)).address
+      );
+    }
+    const payeeAccounts = payees.map((pubkey) => ({ pubkey, isWritable: true, isSigner: false }));
+
+    const setRevenueSplits = (splits: { tokenAccount: PublicKey; bps: number }[], accounts = payeeAccounts) =>
+      vaultpayProgram.methods
+        .setRevenueSplits(splits)
+        .accountsPartial({
+          vendorSigner: vendorAuthority.publicKey,
+          tokenMint,
+          config: configPDA,
+          vendor: vendorPDA,
+        })
+        .remainingAccounts(accounts)
+        .signers([vendorAuthority])
+        .rpc();
+
+    const splits = [
+      { tokenAccount: payees[0], bps: 3_333 },
+      { tokenAccount: payees[1], bps: 3_333 },
+      { tokenAccount: payees[2], bps: 3_334 },
+    ];
+    await expectError(setRevenueSplits(splits, payeeAccounts.slice(0, 2)), "InvalidRevenueSplitAccounts");
+    await setRevenueSplits(splits);
+
+    // An odd price leaves a remainder: 1.000001 tokens less the 5% fee is 950_001 for the payees
+    const oddPlan = await createPlan(26, 1_000_001, 4, null);
+    const subscriber = await setupSubscriber(500_000_000);
+    const subscription = await subscribe(subscriber, new BN(2_401), oddPlan);
+    const vendorBefore = await balanceOf(vendorPdaAta);
+
+    await vaultpayProgram.methods
+      .processPayment()
+      .accountsPartial({
+        vendorSigner: vendorAuthority.publicKey,
+        ...chargeAccounts(subscriber, subscription),
+      })
+      .remainingAccounts(payeeAccounts)
+      .signers([vendorAuthority])
+      .rpc();
+
+    const shares = [316_635, 316_635, 316_731];
+    for (let i = 0; i < payees.length; i++) {
+      assert.equal(await balanceOf(payees[i]), shares[i]);
+    }
+    assert.equal(await balanceOf(vendorPdaAta), vendorBefore, "Split revenue bypasses the vendor wallet");
+
+    // Batches take the payees once, after the entries
+    await sleep(5000);
+    await vaultpayProgram.methods
+      .processPaymentsBatch(1)
+      .accountsPartial({
+        vendorSigner: vendorAuthority.publicKey,
+        tokenMint,
+        config: configPDA,
+        vendor: vendorPDA,
+        yieldReserve: yieldReservePDA,
+        vendorTokenAccount: vendorPdaAta,
+        reserveTokenAccount: reserveTokenAccount,
+        treasuryTokenAccount: treasuryTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts([
+        ...[
+          subscription,
+          subscriber.vaultpayAuthority,
+          subscriber.yieldAccount,
+          subscriber.yieldTokenAccount,
+          subscriber.vaultpayAuthorityAta,
+          subscriber.userVault,
+        ].map((pubkey) => ({ pubkey, isWritable: true, isSigner: false })),
+        ...payeeAccounts,
+      ])
+      .signers([vendorAuthority])
+      .rpc();
+    for (let i = 0; i < payees.length; i++) {
+      assert.equal(await balanceOf(payees[i]), 2 * shares[i]);
+    }
+
+    // Refunds come from the vendor's own account, the vendor wallet received nothing
+    const refundSource = (
+      await getOrCreateAssociatedTokenAccount(provider.connection, authority, tokenMint, vendorAuthority.publicKey)
+    ).address;
+    await mintTo(provider.connection, authority, tokenMint, refundSource, authority, 1_000_001);
+    const userBalanceBefore = await balanceOf(subscriber.tokenAccount);
+
+    await vaultpayProgram.methods
+      .vendorCancelSubscription({ serviceDiscontinued: {} }, true)
+      .accountsPartial({
+        vendorSigner: vendorAuthority.publicKey,
+        tokenMint,
+        config: configPDA,
+        vendor: vendorPDA,
+        subscription,
+        vendorTokenAccount: vendorPdaAta,
+        userTokenAccount: subscriber.tokenAccount,
+        refundSource,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([vendorAuthority])
+      .rpc();
+    const refund = (await balanceOf(subscriber.tokenAccount)) - userBalanceBefore;
+    assert.isAtMost(refund, 1_000_001);
+    assert.equal(await balanceOf(refundSource), 1_000_001 - refund);
+
+    await setRevenueSplits([], []);
+  });
+
+  it("Credits referral shares to the affiliate escrow on upfront and prorated charges", async () => {
+    // Referrers earn 20% of the platform fee on a subscription's first two payments
+    await updateConfig({ referralFee: 2_000, referralPayments: 2 });
+
+    const wallet = Keypair.generate();
+    const [affiliate] = PublicKey.findProgramAddressSync(
+      [Buffer.from("affiliate"), configPDA.toBuffer(), wallet.publicKey.toBuffer()],
+      vaultpayProgram.programId
+    );
+    const affiliateEscrow = getAssociatedTokenAddressSync(tokenMint, affiliate, true);
+    await vaultpayProgram.methods
+      .registerAffiliate(wallet.publicKey)
+      .accountsPartial({
+        authority: authority.publicKey,
+        tokenMint,
+        config: configPDA,
+        affiliate,
+        affiliateEscrow,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+    const referral = { affiliate, affiliateEscrow };
+
+    // 100 token upfront charge: 5 token platform fee, 1 of which goes to the referrer
+    const subscriber = await setupSubscriber(500_000_000);
+    const subscriptionSeed = new BN(2_501);
+    await subscribeWithPayment(subscriber, subscriptionSeed, referral);
+    const subscription = subscriptionAddress(subscriber.keypair.publicKey, subscriptionSeed);
+    const subscriptionAccount = await vaultpayProgram.account.subscription.fetch(subscription);
+    assert.ok(subscriptionAccount.referrer.equals(affiliate));
+    assert.equal(await balanceOf(affiliateEscrow), 1_000_000);
+
+    // Upgrading pays about 100 tokens for the rest of the period, another 1 token share
+    const pricierPlan = await createPlan(27, 200_000_000, 30 * 24 * 60 * 60, 3);
+    await changePlan(subscriber, subscription, pricierPlan, referral);
+    const earned = await balanceOf(affiliateEscrow);
+    assert.isAbove(earned, 1_990_000);
+    assert.isAtMost(earned, 2_000_000);
+    let affiliateAccount = await vaultpayProgram.account.affiliate.fetch(affiliate);
+    assert.equal(affiliateAccount.totalEarned.toNumber(), earned);
+
+    const destinationTokenAccount = (
+      await getOrCreateAssociatedTokenAccount(provider.connection, authority, tokenMint, wallet.publicKey)
+    ).address;
+    const claim = () =>
+      vaultpayProgram.methods
+        .claimAffiliateEarnings()
+        .accountsPartial({
+          wallet: wallet.publicKey,
+          tokenMint,
+          config: configPDA,
+          affiliate,
+          affiliateEscrow,
+          destinationTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([wallet])
+        .rpc();
+
+    await claim();
+    assert.equal(await balanceOf(destinationTokenAccount), earned);
+    assert.equal(await balanceOf(affiliateEscrow), 0);
+    affiliateAccount = await vaultpayProgram.account.affiliate.fetch(affiliate);
+    assert.equal(affiliateAccount.totalClaimed.toNumber(), earned);
+    await expectError(claim(), "NothingToWithdraw");
+
+    await updateConfig({ referralFee: 0, referralPayments: 0 });
+  });
 });